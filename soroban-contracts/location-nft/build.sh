@@ -25,9 +25,17 @@ rustup target add wasm32v1-none
 # Build the contract
 echo "🔨 Building contract for wasm32v1-none target..."
 cargo build --target wasm32v1-none --release
+STATUS=$?
+
+# With --test-wasms, also build the next storage version for the upgrade tests
+if [ $STATUS -eq 0 ] && [ "$1" == "--test-wasms" ]; then
+    echo "🔨 Building next-version contract for the upgrade tests..."
+    cargo build --target wasm32v1-none --release --features next-version --target-dir target/next
+    STATUS=$?
+fi
 
 # Check if build was successful
-if [ $? -eq 0 ]; then
+if [ $STATUS -eq 0 ]; then
     echo "✅ Contract built successfully!"
     echo "📁 WASM file location: target/wasm32v1-none/release/location_nft.wasm"
     
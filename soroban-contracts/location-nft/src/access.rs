@@ -0,0 +1,58 @@
+//! Roles that delegate part of the admin's authority. The admin itself is
+//! the ADMIN slot, rotated with `set_admin`/`accept_admin`, and may call
+//! every role-gated entrypoint.
+//!
+//! Grants are persistent entries, so their number does not weigh on the
+//! instance. They are extended when granted and whenever they are used.
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use soroban_sdk::{contracttype, symbol_short, Address, Env};
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// May call `mint` and `mint_batch`.
+    Minter,
+    /// May call `update_location`, `set_validity` and `set_polygon`.
+    LocationUpdater,
+}
+
+pub fn has_role(env: &Env, role: Role, account: &Address) -> bool {
+    let role_key = (role, account.clone(), symbol_short!("ROLE"));
+    env.storage().persistent().has(&role_key)
+}
+
+/// Returns true if `account` holds `role`, extending the grant's TTL.
+pub fn use_role(env: &Env, role: Role, account: &Address) -> bool {
+    if !has_role(env, role, account) {
+        return false;
+    }
+    let role_key = (role, account.clone(), symbol_short!("ROLE"));
+    env.storage()
+        .persistent()
+        .extend_ttl(&role_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    true
+}
+
+/// Grants `role` to `account`; returns false if it already held it, in which
+/// case the grant is only extended.
+pub fn grant_role(env: &Env, role: Role, account: &Address) -> bool {
+    if use_role(env, role, account) {
+        return false;
+    }
+    let role_key = (role, account.clone(), symbol_short!("ROLE"));
+    env.storage().persistent().set(&role_key, &true);
+    env.storage()
+        .persistent()
+        .extend_ttl(&role_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    true
+}
+
+/// Revokes `role` from `account`; returns false if it did not hold it.
+pub fn revoke_role(env: &Env, role: Role, account: &Address) -> bool {
+    if !has_role(env, role, account) {
+        return false;
+    }
+    let role_key = (role, account.clone(), symbol_short!("ROLE"));
+    env.storage().persistent().remove(&role_key);
+    true
+}
@@ -0,0 +1,87 @@
+//! Per-token approvals and operator approvals. Both expire at a ledger
+//! sequence and live in temporary storage, following the token contract's
+//! allowance model.
+use crate::Error;
+use soroban_sdk::{contracttype, symbol_short, Address, Env};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApprovalValue {
+    pub approved: Address,
+    pub expiration_ledger: u32,
+}
+
+fn check_expiration(e: &Env, expiration_ledger: u32) -> Result<u32, Error> {
+    expiration_ledger
+        .checked_sub(e.ledger().sequence())
+        .ok_or(Error::InvalidExpiration)
+}
+
+/// Returns the address approved for `token_id`, if the approval is live.
+pub fn read_approval(e: &Env, token_id: u32) -> Option<Address> {
+    let key = (token_id, symbol_short!("APPROVAL"));
+    let approval: ApprovalValue = e.storage().temporary().get(&key)?;
+    if approval.expiration_ledger < e.ledger().sequence() {
+        None
+    } else {
+        Some(approval.approved)
+    }
+}
+
+/// Approves `approved` for `token_id` until `expiration_ledger`; an
+/// expiration of 0 removes the approval instead.
+pub fn write_approval(
+    e: &Env,
+    token_id: u32,
+    approved: Address,
+    expiration_ledger: u32,
+) -> Result<(), Error> {
+    if expiration_ledger == 0 {
+        remove_approval(e, token_id);
+        return Ok(());
+    }
+    let live_for = check_expiration(e, expiration_ledger)?;
+
+    let key = (token_id, symbol_short!("APPROVAL"));
+    let approval = ApprovalValue {
+        approved,
+        expiration_ledger,
+    };
+    e.storage().temporary().set(&key, &approval);
+    e.storage().temporary().extend_ttl(&key, live_for, live_for);
+    Ok(())
+}
+
+pub fn remove_approval(e: &Env, token_id: u32) {
+    let key = (token_id, symbol_short!("APPROVAL"));
+    e.storage().temporary().remove(&key);
+}
+
+/// Returns true if `operator` may manage all of `owner`'s tokens.
+pub fn is_operator(e: &Env, owner: Address, operator: Address) -> bool {
+    let key = (owner, operator, symbol_short!("OPERATOR"));
+    match e.storage().temporary().get::<_, u32>(&key) {
+        Some(expiration_ledger) => expiration_ledger >= e.ledger().sequence(),
+        None => false,
+    }
+}
+
+/// Approves `operator` for all of `owner`'s tokens until
+/// `expiration_ledger`; an expiration of 0 removes the approval instead.
+pub fn write_operator(
+    e: &Env,
+    owner: Address,
+    operator: Address,
+    expiration_ledger: u32,
+) -> Result<(), Error> {
+    let key = (owner, operator, symbol_short!("OPERATOR"));
+    if expiration_ledger == 0 {
+        e.storage().temporary().remove(&key);
+        return Ok(());
+    }
+    let live_for = check_expiration(e, expiration_ledger)?;
+
+    e.storage().temporary().set(&key, &expiration_ledger);
+    e.storage().temporary().extend_ttl(&key, live_for, live_for);
+    Ok(())
+}
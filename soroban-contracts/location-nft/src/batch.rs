@@ -0,0 +1,85 @@
+//! Batch size limits.
+//!
+//! A transaction's footprint lists every ledger entry it reads or writes,
+//! and the network caps both its size and the number of entries written.
+//! Each batch entrypoint's limit is derived from the entries one call
+//! accesses regardless of size plus those each item adds, counted for the
+//! worst case. `test_batch_footprint` measures both, so a storage change
+//! that adds entries fails it until these counts, and with them the limits,
+//! are updated.
+
+/// The network's `txMaxFootprintEntries`: read-only plus read-write entries
+/// in a transaction's footprint, each listed once (100 on mainnet).
+const MAX_TX_ENTRIES: u32 = 100;
+/// The network's `txMaxWriteLedgerEntries`: entries a transaction may write
+/// (50 on mainnet).
+const MAX_TX_WRITES: u32 = 50;
+
+/// Ledger entries accessed by a batch call.
+pub(crate) struct Footprint {
+    /// Entries accessed once per call.
+    pub shared: u32,
+    /// Of `shared`, the entries written.
+    pub shared_writes: u32,
+    /// Entries accessed for each item.
+    pub per_item: u32,
+    /// Of `per_item`, the entries written.
+    pub per_item_writes: u32,
+}
+
+impl Footprint {
+    /// Entries in the footprint of a call with `items` items.
+    pub const fn accessed(&self, items: u32) -> u32 {
+        self.shared + items * self.per_item
+    }
+
+    /// Entries written by a call with `items` items.
+    pub const fn writes(&self, items: u32) -> u32 {
+        self.shared_writes + items * self.per_item_writes
+    }
+
+    /// Largest number of items whose footprint fits in one transaction.
+    pub const fn max_items(&self) -> u32 {
+        let mut items = 0;
+        while self.accessed(items + 1) <= MAX_TX_ENTRIES && self.writes(items + 1) <= MAX_TX_WRITES
+        {
+            items += 1;
+        }
+        items
+    }
+}
+
+/// `mint_batch` accesses the contract's instance and code, the minter's
+/// authorization and, unless the minter is the admin, its role grant. Each
+/// soulbound token minted to a new owner in a new cell writes 12 entries: its
+/// owner, metadata, location and soulbound flag; its slot and reverse entry
+/// in the owner index and the owner's balance; its slot and reverse entry in
+/// the cell index and the cell's length; its first history record and the
+/// history length.
+pub(crate) const MINT_FOOTPRINT: Footprint = Footprint {
+    shared: 5,
+    shared_writes: 2,
+    per_item: 12,
+    per_item_writes: 12,
+};
+
+/// `transfer_batch` accesses the contract's instance and code, the sender's
+/// authorization and both owners' balances. Each token that has every
+/// optional record, and whose removal moves another of the sender's tokens,
+/// accesses 16 entries. It writes 7: its owner and approval; its reverse
+/// entry, its old and new slots in the owner index, and the sender's last
+/// slot and the reverse entry of the token moved from it. It reads 9, which
+/// are extended: its metadata, location, soulbound flag, frozen flag,
+/// royalty and polygon, and its cell-index slot and reverse entry and the
+/// cell's length.
+pub(crate) const TRANSFER_FOOTPRINT: Footprint = Footprint {
+    shared: 6,
+    shared_writes: 3,
+    per_item: 16,
+    per_item_writes: 7,
+};
+
+/// Largest batch accepted by `mint_batch`.
+pub(crate) const MAX_MINT_BATCH: u32 = MINT_FOOTPRINT.max_items();
+/// Largest batch accepted by `transfer_batch`.
+pub(crate) const MAX_TRANSFER_BATCH: u32 = TRANSFER_FOOTPRINT.max_items();
@@ -0,0 +1,65 @@
+//! Collection-level metadata, shared by every token instead of being repeated
+//! in each token's record.
+use crate::Error;
+use soroban_sdk::{contracttype, symbol_short, Env, String};
+
+/// Longest base URI accepted, in bytes, so a token URI fits in `URI_BUFFER`.
+const MAX_BASE_URI_LEN: u32 = 200;
+/// Room for the base URI and the ten decimal digits of a `u32` token id.
+const URI_BUFFER: usize = 256;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CollectionMetadata {
+    pub description: String,
+    pub image: String,
+    pub external_url: String,
+    pub base_uri: String,
+}
+
+/// Returns the collection metadata, with every field empty until it is set.
+pub fn read_collection_metadata(env: &Env) -> CollectionMetadata {
+    env.storage()
+        .instance()
+        .get(&symbol_short!("COLLMETA"))
+        .unwrap_or_else(|| CollectionMetadata {
+            description: String::from_str(env, ""),
+            image: String::from_str(env, ""),
+            external_url: String::from_str(env, ""),
+            base_uri: String::from_str(env, ""),
+        })
+}
+
+pub fn write_collection_metadata(env: &Env, metadata: &CollectionMetadata) -> Result<(), Error> {
+    if metadata.base_uri.len() > MAX_BASE_URI_LEN {
+        return Err(Error::InvalidUri);
+    }
+    env.storage()
+        .instance()
+        .set(&symbol_short!("COLLMETA"), metadata);
+    Ok(())
+}
+
+/// Returns `base_uri` followed by `token_id` in decimal.
+pub fn compose_token_uri(env: &Env, base_uri: &String, token_id: u32) -> String {
+    let mut buf = [0u8; URI_BUFFER];
+    let base_len = base_uri.len() as usize;
+    base_uri.copy_into_slice(&mut buf[..base_len]);
+
+    let mut digits = [0u8; 10];
+    let mut digit_count = 0;
+    let mut rest = token_id;
+    loop {
+        digits[digit_count] = b'0' + (rest % 10) as u8;
+        digit_count += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    for (i, digit) in digits[..digit_count].iter().rev().enumerate() {
+        buf[base_len + i] = *digit;
+    }
+
+    String::from_bytes(env, &buf[..base_len + digit_count])
+}
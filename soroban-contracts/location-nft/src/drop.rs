@@ -0,0 +1,99 @@
+//! Location-gated drops: NFTs that a user can only claim while an oracle
+//! attests they are standing inside the drop's radius.
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use crate::Error;
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, String};
+
+/// How old, in seconds, a position attestation may be when it is redeemed.
+pub(crate) const MAX_ATTESTATION_AGE: u64 = 300;
+/// How far, in seconds, an attestation may be ahead of the ledger clock.
+pub(crate) const MAX_CLOCK_SKEW: u64 = 60;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LocationDrop {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub latitude: i64,  // Microdegrees (degrees * 1,000,000)
+    pub longitude: i64, // Microdegrees (degrees * 1,000,000)
+    pub radius: u32,
+    pub supply: u32,
+    pub claimed: u32,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+/// A position observed by the oracle. The oracle signs the XDR encoding of
+/// this struct with the ed25519 key configured through `set_oracle`.
+/// `contract` is the LocationNFT contract the attestation is redeemed on, so
+/// an oracle shared by several collections cannot have its attestations
+/// replayed on another collection's drop with the same id.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionAttestation {
+    pub contract: Address,
+    pub claimer: Address,
+    pub drop_id: u32,
+    pub latitude: i64,
+    pub longitude: i64,
+    pub timestamp: u64,
+}
+
+pub fn read_oracle(env: &Env) -> Result<BytesN<32>, Error> {
+    env.storage()
+        .instance()
+        .get(&symbol_short!("ORACLE"))
+        .ok_or(Error::OracleNotSet)
+}
+
+pub fn write_oracle(env: &Env, oracle: &BytesN<32>) {
+    env.storage()
+        .instance()
+        .set(&symbol_short!("ORACLE"), oracle);
+}
+
+/// Allocates the next drop id, starting at 1.
+pub fn next_drop_id(env: &Env) -> u32 {
+    let drop_id = env
+        .storage()
+        .instance()
+        .get::<_, u32>(&symbol_short!("DROPS"))
+        .unwrap_or(0)
+        + 1;
+    env.storage()
+        .instance()
+        .set(&symbol_short!("DROPS"), &drop_id);
+    drop_id
+}
+
+pub fn read_drop(env: &Env, drop_id: u32) -> Result<LocationDrop, Error> {
+    let drop_key = (drop_id, symbol_short!("DROP"));
+    env.storage()
+        .persistent()
+        .get(&drop_key)
+        .ok_or(Error::DropNotFound)
+}
+
+pub fn write_drop(env: &Env, drop_id: u32, drop: &LocationDrop) {
+    let drop_key = (drop_id, symbol_short!("DROP"));
+    env.storage().persistent().set(&drop_key, drop);
+    env.storage()
+        .persistent()
+        .extend_ttl(&drop_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+pub fn has_claimed(env: &Env, drop_id: u32, claimer: &Address) -> bool {
+    let claimed_key = (drop_id, claimer.clone(), symbol_short!("CLAIMED"));
+    env.storage().persistent().has(&claimed_key)
+}
+
+pub fn set_claimed(env: &Env, drop_id: u32, claimer: &Address) {
+    let claimed_key = (drop_id, claimer.clone(), symbol_short!("CLAIMED"));
+    env.storage().persistent().set(&claimed_key, &true);
+    env.storage().persistent().extend_ttl(
+        &claimed_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+}
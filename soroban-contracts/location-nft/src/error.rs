@@ -0,0 +1,46 @@
+use soroban_sdk::contracterror;
+
+/// Errors returned by the LocationNFT contract.
+///
+/// Codes 2-4 keep the values the contract used before this enum existed, so
+/// clients that already map those raw codes keep working.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    /// Returned by `import_legacy` for tokens that were already imported.
+    TokenExists = 2,
+    NotOwner = 3,
+    TokenNotFound = 4,
+    NotInitialized = 5,
+    InvalidCoordinate = 6,
+    NoPendingAdmin = 7,
+    InvalidRadius = 8,
+    OracleNotSet = 9,
+    DropNotFound = 10,
+    DropNotActive = 11,
+    DropExhausted = 12,
+    AlreadyClaimed = 13,
+    InvalidAttestation = 14,
+    AttestationExpired = 15,
+    OutOfRange = 16,
+    InvalidTimeWindow = 17,
+    NotApproved = 18,
+    InvalidExpiration = 19,
+    BatchTooLarge = 20,
+    MetadataFrozen = 21,
+    InvalidRoyalty = 22,
+    RoyaltyNotSet = 23,
+    AlreadyMigrated = 24,
+    TokenNotActive = 25,
+    Soulbound = 26,
+    InvalidUri = 27,
+    InvalidPolygon = 28,
+    PolygonNotSet = 29,
+    Paused = 30,
+    NotPaused = 31,
+    MissingRole = 32,
+    /// No migration exists to a version, so the code cannot migrate.
+    UnknownVersion = 33,
+}
@@ -0,0 +1,171 @@
+//! Events published by the LocationNFT contract.
+use crate::access::Role;
+use soroban_sdk::{contractevent, Address, BytesN, String, Vec};
+
+// Admin rotation is two-step: the current admin proposes a successor, which
+// only takes over once it accepts, so a typo cannot lock the contract.
+#[contractevent(data_format = "single-value")]
+pub struct AdminProposed {
+    #[topic]
+    pub admin: Address,
+    pub pending_admin: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct SetAdmin {
+    #[topic]
+    pub admin: Address,
+    pub new_admin: Address,
+}
+
+#[contractevent]
+pub struct Mint {
+    #[topic]
+    pub to: Address,
+    pub token_id: u32,
+    pub latitude: i64,
+    pub longitude: i64,
+    pub radius: u32,
+}
+
+/// Maps a token imported from a first-release contract to its new id.
+#[contractevent]
+pub struct Imported {
+    #[topic]
+    pub legacy: Address,
+    pub legacy_id: u32,
+    pub token_id: u32,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct Transfer {
+    #[topic]
+    pub from: Address,
+    #[topic]
+    pub to: Address,
+    pub token_id: u32,
+}
+
+#[contractevent]
+pub struct LocationUpdated {
+    #[topic]
+    pub token_id: u32,
+    pub latitude: i64,
+    pub longitude: i64,
+    pub radius: u32,
+    pub updater: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct Burn {
+    #[topic]
+    pub owner: Address,
+    pub token_id: u32,
+}
+
+#[contractevent]
+pub struct Approve {
+    #[topic]
+    pub owner: Address,
+    #[topic]
+    pub token_id: u32,
+    pub approved: Address,
+    pub expiration_ledger: u32,
+}
+
+#[contractevent]
+pub struct ApproveForAll {
+    #[topic]
+    pub owner: Address,
+    pub operator: Address,
+    pub expiration_ledger: u32,
+}
+
+#[contractevent]
+pub struct MetadataUpdated {
+    #[topic]
+    pub token_id: u32,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// `token_id` is `None` when the whole collection is frozen.
+#[contractevent(data_format = "single-value")]
+pub struct MetadataFrozen {
+    #[topic]
+    pub caller: Address,
+    pub token_id: Option<u32>,
+}
+
+/// `token_id` is `None` for the collection's default royalty.
+#[contractevent]
+pub struct RoyaltySet {
+    #[topic]
+    pub token_id: Option<u32>,
+    pub receiver: Address,
+    pub basis_points: u32,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct Upgraded {
+    pub new_wasm_hash: BytesN<32>,
+}
+
+#[contractevent]
+pub struct Migrated {
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+#[contractevent]
+pub struct ValidityUpdated {
+    #[topic]
+    pub token_id: u32,
+    pub valid_from: Option<u64>,
+    pub valid_until: Option<u64>,
+}
+
+#[contractevent]
+pub struct CollectionMetadataUpdated {
+    pub description: String,
+    pub image: String,
+    pub external_url: String,
+    pub base_uri: String,
+}
+
+/// `vertices` is empty when the token's polygon is removed.
+#[contractevent(data_format = "single-value")]
+pub struct PolygonUpdated {
+    #[topic]
+    pub token_id: u32,
+    pub vertices: Vec<(i64, i64)>,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct Paused {
+    pub admin: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct Unpaused {
+    pub admin: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct RoleGranted {
+    #[topic]
+    pub role: Role,
+    #[topic]
+    pub account: Address,
+    pub admin: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+pub struct RoleRevoked {
+    #[topic]
+    pub role: Role,
+    #[topic]
+    pub account: Address,
+    pub admin: Address,
+}
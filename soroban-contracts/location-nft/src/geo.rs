@@ -0,0 +1,201 @@
+//! Deterministic integer geometry on microdegree coordinates, usable from
+//! any `no_std` contract.
+use soroban_sdk::{String, Vec};
+
+/// Number of microdegrees in one degree.
+pub const MICRODEGREES: i64 = 1_000_000;
+pub const MAX_LATITUDE: i64 = 90 * MICRODEGREES;
+pub const MAX_LONGITUDE: i64 = 180 * MICRODEGREES;
+
+/// Largest token radius, in meters, until the admin configures another.
+pub const DEFAULT_MAX_RADIUS: u32 = 100_000;
+
+/// Longest decimal-degree string accepted, e.g. "-180.000000" plus slack.
+const MAX_COORDINATE_LEN: usize = 24;
+
+/// Parses a decimal-degree string such as "-73.9654" into microdegrees.
+///
+/// Digits beyond the sixth decimal place are truncated. Returns `None` for
+/// anything that is not an optionally signed decimal number.
+pub fn parse_microdegrees(value: &String) -> Option<i64> {
+    let len = value.len() as usize;
+    if len == 0 || len > MAX_COORDINATE_LEN {
+        return None;
+    }
+    let mut buf = [0u8; MAX_COORDINATE_LEN];
+    value.copy_into_slice(&mut buf[..len]);
+
+    let (negative, digits) = match buf[0] {
+        b'-' => (true, &buf[1..len]),
+        b'+' => (false, &buf[1..len]),
+        _ => (false, &buf[..len]),
+    };
+
+    let mut whole: i64 = 0;
+    let mut fraction: i64 = 0;
+    let mut fraction_digits = 0;
+    let mut seen_digit = false;
+    let mut seen_point = false;
+    for &c in digits {
+        match c {
+            b'0'..=b'9' => {
+                seen_digit = true;
+                let d = (c - b'0') as i64;
+                if !seen_point {
+                    whole = whole.checked_mul(10)?.checked_add(d)?;
+                } else if fraction_digits < 6 {
+                    fraction = fraction * 10 + d;
+                    fraction_digits += 1;
+                }
+            }
+            b'.' if !seen_point => seen_point = true,
+            _ => return None,
+        }
+    }
+    if !seen_digit {
+        return None;
+    }
+    while fraction_digits < 6 {
+        fraction *= 10;
+        fraction_digits += 1;
+    }
+
+    let micro = whole.checked_mul(MICRODEGREES)?.checked_add(fraction)?;
+    Some(if negative { -micro } else { micro })
+}
+
+/// Returns true if the microdegree pair is a valid position on the globe.
+pub fn is_valid_position(latitude: i64, longitude: i64) -> bool {
+    (-MAX_LATITUDE..=MAX_LATITUDE).contains(&latitude)
+        && (-MAX_LONGITUDE..=MAX_LONGITUDE).contains(&longitude)
+}
+
+/// Millimeters per degree of arc on a sphere with the mean Earth radius
+/// (6,371,008.8 m).
+const MM_PER_DEGREE: i128 = 111_195_080;
+
+/// Fixed-point scale used by `cos_scaled`.
+const COS_SCALE: i128 = 1_000_000_000;
+/// Pi in `COS_SCALE` units.
+const PI_SCALED: i128 = 3_141_592_654;
+
+/// Cosine of an angle given in microdegrees, scaled by `COS_SCALE`.
+///
+/// Uses a Taylor series on [0, 90] degrees, which is exact to well under one
+/// part in 10^8 there; other angles are folded into that range first.
+fn cos_scaled(angle: i64) -> i128 {
+    let mut angle = (angle as i128).abs() % (360 * MICRODEGREES as i128);
+    if angle > 180 * MICRODEGREES as i128 {
+        angle = 360 * MICRODEGREES as i128 - angle;
+    }
+    let negate = angle > 90 * MICRODEGREES as i128;
+    if negate {
+        angle = 180 * MICRODEGREES as i128 - angle;
+    }
+
+    let x = angle * PI_SCALED / (180 * MICRODEGREES as i128);
+    let x2 = x * x / COS_SCALE;
+    let mut term = COS_SCALE;
+    let mut sum = COS_SCALE;
+    for k in 1..=8 {
+        term = -term * x2 / COS_SCALE / ((2 * k - 1) * (2 * k));
+        sum += term;
+    }
+    if negate {
+        -sum
+    } else {
+        sum
+    }
+}
+
+/// Squared equirectangular distance between two positions, in square
+/// millimeters. The longitude difference is taken the short way round, so
+/// positions either side of the antimeridian are close together.
+fn distance_squared_mm(lat1: i64, lng1: i64, lat2: i64, lng2: i64) -> i128 {
+    let mut d_lng = (lng2 as i128) - (lng1 as i128);
+    if d_lng > MAX_LONGITUDE as i128 {
+        d_lng -= 2 * MAX_LONGITUDE as i128;
+    } else if d_lng < -(MAX_LONGITUDE as i128) {
+        d_lng += 2 * MAX_LONGITUDE as i128;
+    }
+    let d_lat = (lat2 as i128) - (lat1 as i128);
+    let mean_lat = ((lat1 as i128 + lat2 as i128) / 2) as i64;
+
+    let y = d_lat * MM_PER_DEGREE / MICRODEGREES as i128;
+    let x = d_lng * MM_PER_DEGREE / MICRODEGREES as i128 * cos_scaled(mean_lat) / COS_SCALE;
+    x * x + y * y
+}
+
+/// Approximate distance in meters between two microdegree positions.
+pub fn distance_meters(lat1: i64, lng1: i64, lat2: i64, lng2: i64) -> u64 {
+    let mm = distance_squared_mm(lat1, lng1, lat2, lng2)
+        .unsigned_abs()
+        .isqrt();
+    (mm / 1000) as u64
+}
+
+/// Returns true if (`lat`, `lng`) lies within `radius` meters of the center.
+pub fn is_within_radius(center_lat: i64, center_lng: i64, radius: u32, lat: i64, lng: i64) -> bool {
+    let radius_mm = radius as i128 * 1000;
+    distance_squared_mm(center_lat, center_lng, lat, lng) <= radius_mm * radius_mm
+}
+
+/// Side of a grid cell in microdegrees (0.001 degrees, about 111 m of
+/// latitude), the backend's default anchor-event cell precision.
+pub const CELL_SIZE: i64 = 1_000;
+
+/// Returns the grid cell containing a position, identified by its south-west
+/// corner in microdegrees.
+///
+/// This is the backend's `calculateCellId` in integer form: the cell the
+/// backend names "34.230000_-118.232000" is `(34_230_000, -118_232_000)`.
+pub fn grid_cell(latitude: i64, longitude: i64) -> (i64, i64) {
+    (
+        latitude.div_euclid(CELL_SIZE) * CELL_SIZE,
+        longitude.div_euclid(CELL_SIZE) * CELL_SIZE,
+    )
+}
+
+/// Returns true if (`lat`, `lng`) lies inside the polygon with the given
+/// (latitude, longitude) vertices, or on its boundary.
+///
+/// Uses an even-odd ray cast on microdegrees, so concave shapes are handled
+/// exactly. Longitudes are taken relative to the first vertex the short way
+/// round, so polygons may cross the antimeridian as long as they span less
+/// than 180 degrees of longitude.
+pub fn is_within_polygon(vertices: &Vec<(i64, i64)>, lat: i64, lng: i64) -> bool {
+    let (Some((_, origin)), Some(last)) = (vertices.first(), vertices.last()) else {
+        return false;
+    };
+    let unwrap = |v_lng: i64| {
+        let d_lng = v_lng - origin;
+        if d_lng > MAX_LONGITUDE {
+            d_lng - 2 * MAX_LONGITUDE
+        } else if d_lng < -MAX_LONGITUDE {
+            d_lng + 2 * MAX_LONGITUDE
+        } else {
+            d_lng
+        }
+    };
+    let point_x = unwrap(lng);
+    // Vertex relative to the point, as (x, y) = (longitude, latitude).
+    let relative = |(v_lat, v_lng): (i64, i64)| (unwrap(v_lng) - point_x, v_lat - lat);
+
+    let (mut x1, mut y1) = relative(last);
+    let mut inside = false;
+    for vertex in vertices.iter() {
+        let (x2, y2) = relative(vertex);
+        // Twice the signed area of the triangle (point, v1, v2); zero when
+        // the point is on the edge's line.
+        let cross = x1 as i128 * y2 as i128 - x2 as i128 * y1 as i128;
+        if cross == 0 && x1.min(x2) <= 0 && 0 <= x1.max(x2) && y1.min(y2) <= 0 && 0 <= y1.max(y2) {
+            return true;
+        }
+        // The edge crosses the ray running east from the point.
+        if (y1 > 0) != (y2 > 0) && (cross > 0) == (y2 > y1) {
+            inside = !inside;
+        }
+        (x1, y1) = (x2, y2);
+    }
+    inside
+}
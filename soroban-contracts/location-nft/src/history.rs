@@ -0,0 +1,92 @@
+//! Append-only log of each token's locations, kept for provenance.
+//!
+//! Each token's log is a dense list of `(token_id, index)` entries plus a
+//! length entry. Entries are never changed or removed, not even by `burn`.
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Vec};
+
+/// One entry in a token's location history.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LocationRecord {
+    pub latitude: i64,  // Microdegrees (degrees * 1,000,000)
+    pub longitude: i64, // Microdegrees (degrees * 1,000,000)
+    pub radius: u32,
+    pub timestamp: u64,
+    /// The address whose authorization wrote the location.
+    pub updater: Address,
+}
+
+fn read_history_len(env: &Env, token_id: u32) -> u32 {
+    let len_key = (token_id, symbol_short!("HIST_LEN"));
+    env.storage().persistent().get(&len_key).unwrap_or(0)
+}
+
+/// Appends a record of `token_id`'s location at the current ledger time.
+pub fn append_history(
+    env: &Env,
+    token_id: u32,
+    latitude: i64,
+    longitude: i64,
+    radius: u32,
+    updater: &Address,
+) {
+    let len = read_history_len(env, token_id);
+    let record = LocationRecord {
+        latitude,
+        longitude,
+        radius,
+        timestamp: env.ledger().timestamp(),
+        updater: updater.clone(),
+    };
+    let history_key = (token_id, len, symbol_short!("HISTORY"));
+    env.storage().persistent().set(&history_key, &record);
+    env.storage().persistent().extend_ttl(
+        &history_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+    let len_key = (token_id, symbol_short!("HIST_LEN"));
+    env.storage().persistent().set(&len_key, &(len + 1));
+    env.storage()
+        .persistent()
+        .extend_ttl(&len_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+/// Extends the TTL of `token_id`'s length entry.
+pub fn extend_history_len(env: &Env, token_id: u32) {
+    let len_key = (token_id, symbol_short!("HIST_LEN"));
+    if env.storage().persistent().has(&len_key) {
+        env.storage().persistent().extend_ttl(
+            &len_key,
+            TOKEN_LIFETIME_THRESHOLD,
+            TOKEN_BUMP_AMOUNT,
+        );
+    }
+}
+
+/// Extends the TTL of up to `limit` of `token_id`'s records, starting at
+/// index `start`.
+pub fn extend_history(env: &Env, token_id: u32, start: u32, limit: u32) {
+    let end = read_history_len(env, token_id).min(start.saturating_add(limit));
+    for index in start..end {
+        let history_key = (token_id, index, symbol_short!("HISTORY"));
+        env.storage().persistent().extend_ttl(
+            &history_key,
+            TOKEN_LIFETIME_THRESHOLD,
+            TOKEN_BUMP_AMOUNT,
+        );
+    }
+}
+
+/// Returns up to `limit` of `token_id`'s records, oldest first, starting at
+/// index `start`.
+pub fn read_history(env: &Env, token_id: u32, start: u32, limit: u32) -> Vec<LocationRecord> {
+    let end = read_history_len(env, token_id).min(start.saturating_add(limit));
+    let mut records = Vec::new(env);
+    for index in start..end {
+        let history_key = (token_id, index, symbol_short!("HISTORY"));
+        records.push_back(env.storage().persistent().get(&history_key).unwrap());
+    }
+    records
+}
@@ -0,0 +1,124 @@
+//! Tokens of the first LocationNFT release.
+//!
+//! The first release kept its tokens in a contract that cannot be upgraded,
+//! storing coordinates as decimal-degree strings; `import_legacy` reads them
+//! through that contract's entrypoints and converts them to microdegrees.
+//! Imported tokens get new ids, and each `(contract, legacy id)` pair maps to
+//! the id it was imported under, so no token is imported twice.
+use crate::geo::{is_valid_position, parse_microdegrees};
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use crate::{Error, LocationData, TokenMetadata};
+use soroban_sdk::{
+    contracttype, symbol_short, Address, Env, IntoVal, String, Symbol, TryFromVal, Val, Vec,
+};
+
+/// Metadata returned by the first release's `get_metadata`.
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyTokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub latitude: String,
+    pub longitude: String,
+    pub radius: u32,
+    pub created_at: u64,
+}
+
+/// Location returned by the first release's `get_location`.
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyLocationData {
+    pub latitude: String,
+    pub longitude: String,
+    pub radius: u32,
+}
+
+/// Parses a legacy position, which was stored without range checks.
+fn parse_position(latitude: &String, longitude: &String) -> Result<(i64, i64), Error> {
+    match (parse_microdegrees(latitude), parse_microdegrees(longitude)) {
+        (Some(lat), Some(lng)) if is_valid_position(lat, lng) => Ok((lat, lng)),
+        _ => Err(Error::InvalidCoordinate),
+    }
+}
+
+/// Calls a read-only entrypoint of the legacy contract, failing with
+/// `TokenNotFound` if it fails or returns something else.
+fn call_legacy<T: TryFromVal<Env, Val>>(
+    env: &Env,
+    legacy: &Address,
+    function: &str,
+    args: Vec<Val>,
+) -> Result<T, Error> {
+    match env.try_invoke_contract::<T, soroban_sdk::Error>(
+        legacy,
+        &Symbol::new(env, function),
+        args,
+    ) {
+        Ok(Ok(value)) => Ok(value),
+        _ => Err(Error::TokenNotFound),
+    }
+}
+
+/// Reads `token_id` from the first-release contract at `legacy` and converts
+/// it to the current layout. Fails with `NotOwner` unless `owner` holds it
+/// there.
+pub fn read_legacy_token(
+    env: &Env,
+    legacy: &Address,
+    owner: &Address,
+    token_id: u32,
+) -> Result<(TokenMetadata, LocationData), Error> {
+    // The first release keys ownership by owner and id, so it can only
+    // confirm an owner, not look one up.
+    let is_owner: bool = call_legacy(
+        env,
+        legacy,
+        "is_owner",
+        (owner.clone(), token_id).into_val(env),
+    )?;
+    if !is_owner {
+        return Err(Error::NotOwner);
+    }
+    let metadata: LegacyTokenMetadata =
+        call_legacy(env, legacy, "get_metadata", (token_id,).into_val(env))?;
+    let location: LegacyLocationData =
+        call_legacy(env, legacy, "get_location", (token_id,).into_val(env))?;
+
+    let (latitude, longitude) = parse_position(&metadata.latitude, &metadata.longitude)?;
+    let metadata = TokenMetadata {
+        name: metadata.name,
+        symbol: metadata.symbol,
+        uri: metadata.uri,
+        latitude,
+        longitude,
+        radius: metadata.radius,
+        created_at: metadata.created_at,
+        updated_at: metadata.created_at,
+    };
+    let (latitude, longitude) = parse_position(&location.latitude, &location.longitude)?;
+    let location = LocationData {
+        latitude,
+        longitude,
+        radius: location.radius,
+        valid_from: None,
+        valid_until: None,
+    };
+    Ok((metadata, location))
+}
+
+/// Returns the id `legacy_id` of the contract at `legacy` was imported under.
+pub fn read_imported(env: &Env, legacy: &Address, legacy_id: u32) -> Option<u32> {
+    let imported_key = (legacy.clone(), legacy_id, symbol_short!("IMPORTED"));
+    env.storage().persistent().get(&imported_key)
+}
+
+pub fn write_imported(env: &Env, legacy: &Address, legacy_id: u32, token_id: u32) {
+    let imported_key = (legacy.clone(), legacy_id, symbol_short!("IMPORTED"));
+    env.storage().persistent().set(&imported_key, &token_id);
+    env.storage().persistent().extend_ttl(
+        &imported_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+}
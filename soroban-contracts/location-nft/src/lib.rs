@@ -1,15 +1,67 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 
+mod access;
+mod approval;
+mod batch;
+mod collection;
+mod drop;
+mod error;
+mod events;
+pub mod geo;
+mod history;
+mod legacy;
+mod ownership;
+mod polygon;
+mod royalty;
+mod spatial;
+mod storage_types;
+mod test;
+mod upgrade;
+
+pub use crate::access::Role;
+pub use crate::collection::CollectionMetadata;
+pub use crate::drop::{LocationDrop, PositionAttestation};
+pub use crate::error::Error;
+pub use crate::history::LocationRecord;
+pub use crate::royalty::Royalty;
+
+use crate::access::{grant_role, has_role, revoke_role, use_role};
+use crate::approval::{
+    is_operator, read_approval, remove_approval, write_approval, write_operator,
+};
+use crate::batch::{MAX_MINT_BATCH, MAX_TRANSFER_BATCH};
+use crate::collection::{compose_token_uri, read_collection_metadata, write_collection_metadata};
+use crate::drop::{
+    has_claimed, next_drop_id, read_drop, read_oracle, set_claimed, write_drop, write_oracle,
+    MAX_ATTESTATION_AGE, MAX_CLOCK_SKEW,
+};
+use crate::events::{
+    AdminProposed, Approve, ApproveForAll, Burn, CollectionMetadataUpdated, Imported,
+    LocationUpdated, MetadataFrozen, MetadataUpdated, Migrated, Mint, Paused, PolygonUpdated,
+    RoleGranted, RoleRevoked, RoyaltySet, SetAdmin, Transfer, Unpaused, Upgraded, ValidityUpdated,
+};
+use crate::geo::{grid_cell, is_valid_position, DEFAULT_MAX_RADIUS};
+use crate::history::{append_history, extend_history, extend_history_len, read_history};
+use crate::legacy::{read_imported, read_legacy_token, write_imported};
+use crate::ownership::{
+    add_token, extend_ownership, has_token, next_token_id, read_balance, read_owner, read_tokens,
+    remove_token,
+};
+use crate::polygon::{read_polygon, remove_polygon, write_polygon};
+use crate::royalty::{read_royalty, royalty_amount, write_default_royalty, write_token_royalty};
+use crate::spatial::{add_to_cell, extend_cell_entries, read_cell_tokens, remove_from_cell};
+use crate::storage_types::{
+    INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD,
+};
+use crate::upgrade::{migrate_step, read_storage_version, write_storage_version, CONTRACT_VERSION};
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Env, String, Val,
+    contract, contractimpl, contracttype, symbol_short, xdr::ToXdr, Address, BytesN, Env, String,
+    Vec,
 };
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct DataKey {
-    pub owner: Address,
-    pub token_id: u32,
-}
+/// Largest page returned by the paginated views.
+const MAX_PAGE_SIZE: u32 = 50;
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -17,18 +69,309 @@ pub struct TokenMetadata {
     pub name: String,
     pub symbol: String,
     pub uri: String,
-    pub latitude: String,
-    pub longitude: String,
+    pub latitude: i64,  // Microdegrees (degrees * 1,000,000)
+    pub longitude: i64, // Microdegrees (degrees * 1,000,000)
     pub radius: u32,
     pub created_at: u64,
+    pub updated_at: u64,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct LocationData {
-    pub latitude: String,
-    pub longitude: String,
+    pub latitude: i64,  // Microdegrees (degrees * 1,000,000)
+    pub longitude: i64, // Microdegrees (degrees * 1,000,000)
     pub radius: u32,
+    pub valid_from: Option<u64>,  // Ledger timestamp; `None` for no start
+    pub valid_until: Option<u64>, // Ledger timestamp; `None` for no end
+}
+
+/// One token to mint in a `mint_batch` call.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MintRequest {
+    pub to: Address,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub latitude: i64,  // Microdegrees (degrees * 1,000,000)
+    pub longitude: i64, // Microdegrees (degrees * 1,000,000)
+    pub radius: u32,
+    pub soulbound: bool,
+}
+
+fn read_admin(env: &Env) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&symbol_short!("ADMIN"))
+        .ok_or(Error::NotInitialized)
+}
+
+fn write_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&symbol_short!("ADMIN"), admin);
+}
+
+fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+/// Fails unless `caller` is the admin or holds `role`.
+fn check_role(env: &Env, caller: &Address, role: Role) -> Result<(), Error> {
+    if *caller != read_admin(env)? && !use_role(env, role, caller) {
+        return Err(Error::MissingRole);
+    }
+    Ok(())
+}
+
+fn is_paused(env: &Env) -> bool {
+    env.storage().instance().has(&symbol_short!("PAUSED"))
+}
+
+/// Fails while the contract is paused. Called by every entrypoint that
+/// changes tokens, drops or approvals.
+fn check_not_paused(env: &Env) -> Result<(), Error> {
+    if is_paused(env) {
+        return Err(Error::Paused);
+    }
+    Ok(())
+}
+
+fn read_max_radius(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&symbol_short!("MAXRADIUS"))
+        .unwrap_or(DEFAULT_MAX_RADIUS)
+}
+
+fn check_location(env: &Env, latitude: i64, longitude: i64, radius: u32) -> Result<(), Error> {
+    if !is_valid_position(latitude, longitude) {
+        return Err(Error::InvalidCoordinate);
+    }
+    if radius > read_max_radius(env) {
+        return Err(Error::InvalidRadius);
+    }
+    Ok(())
+}
+
+fn read_metadata(env: &Env, token_id: u32) -> Result<TokenMetadata, Error> {
+    let metadata_key = (token_id, symbol_short!("METADATA"));
+    env.storage()
+        .persistent()
+        .get(&metadata_key)
+        .ok_or(Error::TokenNotFound)
+}
+
+fn read_location(env: &Env, token_id: u32) -> Result<LocationData, Error> {
+    let location_key = (token_id, symbol_short!("LOCATION"));
+    env.storage()
+        .persistent()
+        .get(&location_key)
+        .ok_or(Error::TokenNotFound)
+}
+
+fn write_metadata(env: &Env, token_id: u32, metadata: &TokenMetadata) {
+    let metadata_key = (token_id, symbol_short!("METADATA"));
+    env.storage().persistent().set(&metadata_key, metadata);
+    env.storage().persistent().extend_ttl(
+        &metadata_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+}
+
+/// Returns true if the ledger time is inside the location's validity window.
+fn is_location_active(env: &Env, location: &LocationData) -> bool {
+    let now = env.ledger().timestamp();
+    location.valid_from.is_none_or(|from| now >= from)
+        && location.valid_until.is_none_or(|until| now <= until)
+}
+
+fn write_location(env: &Env, token_id: u32, location: &LocationData) {
+    let location_key = (token_id, symbol_short!("LOCATION"));
+    env.storage().persistent().set(&location_key, location);
+    env.storage().persistent().extend_ttl(
+        &location_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+}
+
+/// Extends the TTL of `token_id`'s current state: every persistent entry
+/// belonging to it except its location history.
+fn extend_token_state(env: &Env, token_id: u32) -> Result<(), Error> {
+    let storage = env.storage().persistent();
+    let metadata_key = (token_id, symbol_short!("METADATA"));
+    storage.extend_ttl(&metadata_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let location_key = (token_id, symbol_short!("LOCATION"));
+    storage.extend_ttl(&location_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    for key in [
+        symbol_short!("FROZEN"),
+        symbol_short!("ROYALTY"),
+        symbol_short!("SOULBOUND"),
+        symbol_short!("POLYGON"),
+    ] {
+        let optional_key = (token_id, key);
+        if storage.has(&optional_key) {
+            storage.extend_ttl(&optional_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+        }
+    }
+    extend_ownership(env, token_id);
+    let location = read_location(env, token_id)?;
+    let cell = grid_cell(location.latitude, location.longitude);
+    extend_cell_entries(env, cell, token_id);
+    Ok(())
+}
+
+/// Extends the TTL of every persistent entry belonging to `token_id`,
+/// including its whole location history.
+/// Returns true if `token_id`'s metadata, or the whole collection's, is frozen.
+fn is_metadata_frozen(env: &Env, token_id: u32) -> bool {
+    env.storage().instance().has(&symbol_short!("FROZEN"))
+        || env
+            .storage()
+            .persistent()
+            .has(&(token_id, symbol_short!("FROZEN")))
+}
+
+/// Returns true if tokens are minted soulbound by default.
+fn read_default_soulbound(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&symbol_short!("SOULBOUND"))
+        .unwrap_or(false)
+}
+
+/// Returns true if `token_id` was minted soulbound.
+fn is_soulbound(env: &Env, token_id: u32) -> bool {
+    env.storage()
+        .persistent()
+        .has(&(token_id, symbol_short!("SOULBOUND")))
+}
+
+/// Stores a new token's ownership, metadata and location records under the
+/// next token id, which is returned. The token is soulbound if `soulbound`
+/// is set or the collection mints soulbound tokens by default. `minter` is
+/// recorded as the first entry's updater in the token's location history.
+fn write_token(
+    env: &Env,
+    minter: &Address,
+    to: &Address,
+    name: String,
+    symbol: String,
+    uri: String,
+    latitude: i64,
+    longitude: i64,
+    radius: u32,
+    soulbound: bool,
+) -> Result<u32, Error> {
+    check_location(env, latitude, longitude, radius)?;
+
+    let token_id = next_token_id(env);
+    let metadata = TokenMetadata {
+        name,
+        symbol,
+        uri,
+        latitude,
+        longitude,
+        radius,
+        created_at: env.ledger().timestamp(),
+        updated_at: env.ledger().timestamp(),
+    };
+    let location_data = LocationData {
+        latitude,
+        longitude,
+        radius,
+        valid_from: None,
+        valid_until: None,
+    };
+    store_token(
+        env,
+        minter,
+        to,
+        token_id,
+        &metadata,
+        &location_data,
+        soulbound,
+    );
+
+    Ok(token_id)
+}
+
+/// Stores a token's ownership, metadata and location records under
+/// `token_id`, indexes it and counts it in the supply.
+fn store_token(
+    env: &Env,
+    minter: &Address,
+    to: &Address,
+    token_id: u32,
+    metadata: &TokenMetadata,
+    location: &LocationData,
+    soulbound: bool,
+) {
+    add_token(env, to, token_id);
+    write_metadata(env, token_id, metadata);
+    write_location(env, token_id, location);
+    let (latitude, longitude, radius) = (location.latitude, location.longitude, location.radius);
+    add_to_cell(env, grid_cell(latitude, longitude), token_id);
+    append_history(env, token_id, latitude, longitude, radius, minter);
+
+    if soulbound || read_default_soulbound(env) {
+        let soulbound_key = (token_id, symbol_short!("SOULBOUND"));
+        env.storage().persistent().set(&soulbound_key, &true);
+        env.storage().persistent().extend_ttl(
+            &soulbound_key,
+            TOKEN_LIFETIME_THRESHOLD,
+            TOKEN_BUMP_AMOUNT,
+        );
+    }
+
+    // Increment total supply
+    let current_supply: u32 = env
+        .storage()
+        .instance()
+        .get(&symbol_short!("SUPPLY"))
+        .unwrap_or(0);
+    env.storage()
+        .instance()
+        .set(&symbol_short!("SUPPLY"), &(current_supply + 1));
+
+    Mint {
+        to: to.clone(),
+        token_id,
+        latitude,
+        longitude,
+        radius,
+    }
+    .publish(env);
+}
+
+/// Fails unless `token_id` exists and is owned by `from`.
+fn check_owner(env: &Env, from: &Address, token_id: u32) -> Result<(), Error> {
+    match read_owner(env, token_id) {
+        None => Err(Error::TokenNotFound),
+        Some(owner) if owner != *from => Err(Error::NotOwner),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Moves a token to a new owner, clearing any approval on it. Soulbound
+/// tokens cannot be moved.
+fn move_token(env: &Env, from: Address, to: Address, token_id: u32) -> Result<(), Error> {
+    if is_soulbound(env, token_id) {
+        return Err(Error::Soulbound);
+    }
+    remove_approval(env, token_id);
+    remove_token(env, &from, token_id);
+    add_token(env, &to, token_id);
+    // The history grows without bound, so it is left to `bump_token` to keep
+    // each transfer within a fixed footprint (see `batch`).
+    extend_token_state(env, token_id)?;
+
+    Transfer { from, to, token_id }.publish(env);
+
+    Ok(())
 }
 
 #[contract]
@@ -37,176 +380,997 @@ pub struct LocationNFT;
 #[contractimpl]
 impl LocationNFT {
     /// Initialize the contract with admin, name, and symbol
-    pub fn initialize(env: &Env, admin: Address, name: String, symbol: String) {
+    pub fn initialize(
+        env: &Env,
+        admin: Address,
+        name: String,
+        symbol: String,
+    ) -> Result<(), Error> {
+        if env.storage().instance().has(&symbol_short!("ADMIN")) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        write_admin(env, &admin);
+        env.storage().instance().set(&symbol_short!("NAME"), &name);
         env.storage()
             .instance()
-            .set(&symbol_short!("ADMIN"), &admin);
+            .set(&symbol_short!("SYMBOL"), &symbol);
         env.storage()
             .instance()
-            .set(&symbol_short!("NAME"), &name);
+            .set(&symbol_short!("SUPPLY"), &0u32);
+        write_storage_version(env, CONTRACT_VERSION);
+        extend_instance(env);
+
+        Ok(())
+    }
+
+    /// Replace the contract's code, keeping its storage (admin only); call
+    /// `migrate` once the new code is installed
+    pub fn upgrade(env: &Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        Upgraded { new_wasm_hash }.publish(env);
+
+        Ok(())
+    }
+
+    /// Bring storage written by earlier code up to this code's layout
+    /// (admin only); runs once per upgrade
+    pub fn migrate(env: &Env) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+
+        let from_version = read_storage_version(env);
+        if from_version >= CONTRACT_VERSION {
+            return Err(Error::AlreadyMigrated);
+        }
+        for version in from_version + 1..=CONTRACT_VERSION {
+            migrate_step(env, version)?;
+        }
+        write_storage_version(env, CONTRACT_VERSION);
+        Migrated {
+            from_version,
+            to_version: CONTRACT_VERSION,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Get the storage layout version of the running code
+    pub fn version() -> u32 {
+        CONTRACT_VERSION
+    }
+
+    /// Grant a role to an account (admin only)
+    pub fn grant_role(env: &Env, role: Role, account: Address) -> Result<(), Error> {
+        let admin = read_admin(env)?;
+        admin.require_auth();
+        extend_instance(env);
+
+        if grant_role(env, role, &account) {
+            RoleGranted {
+                role,
+                account,
+                admin,
+            }
+            .publish(env);
+        }
+
+        Ok(())
+    }
+
+    /// Revoke a role from an account (admin only)
+    pub fn revoke_role(env: &Env, role: Role, account: Address) -> Result<(), Error> {
+        let admin = read_admin(env)?;
+        admin.require_auth();
+        extend_instance(env);
+
+        if revoke_role(env, role, &account) {
+            RoleRevoked {
+                role,
+                account,
+                admin,
+            }
+            .publish(env);
+        }
+
+        Ok(())
+    }
+
+    /// Check whether an account holds a role. The admin is not listed, but
+    /// may call every role-gated entrypoint
+    pub fn has_role(env: &Env, role: Role, account: Address) -> bool {
+        has_role(env, role, &account)
+    }
+
+    /// Halt every entrypoint that changes tokens, drops or approvals until
+    /// `unpause` is called (admin only)
+    pub fn pause(env: &Env) -> Result<(), Error> {
+        let admin = read_admin(env)?;
+        admin.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
         env.storage()
             .instance()
-            .set(&symbol_short!("SYMBOL"), &symbol);
+            .set(&symbol_short!("PAUSED"), &true);
+        Paused { admin }.publish(env);
+
+        Ok(())
+    }
+
+    /// Resume normal operation after `pause` (admin only)
+    pub fn unpause(env: &Env) -> Result<(), Error> {
+        let admin = read_admin(env)?;
+        admin.require_auth();
+        extend_instance(env);
+        if !is_paused(env) {
+            return Err(Error::NotPaused);
+        }
+
+        env.storage().instance().remove(&symbol_short!("PAUSED"));
+        Unpaused { admin }.publish(env);
+
+        Ok(())
+    }
+
+    /// Check whether the contract is paused
+    pub fn paused(env: &Env) -> bool {
+        is_paused(env)
+    }
+
+    /// Get the current admin
+    pub fn admin(env: &Env) -> Result<Address, Error> {
+        read_admin(env)
+    }
+
+    /// Get the admin proposed by `set_admin` that has not accepted yet
+    pub fn pending_admin(env: &Env) -> Option<Address> {
+        env.storage().instance().get(&symbol_short!("PENDING"))
+    }
+
+    /// Propose a new admin (admin only); takes effect once `accept_admin` is called
+    pub fn set_admin(env: &Env, new_admin: Address) -> Result<(), Error> {
+        let admin = read_admin(env)?;
+        admin.require_auth();
+        extend_instance(env);
+
         env.storage()
             .instance()
-            .set(&symbol_short!("SUPPLY"), &0u32);
+            .set(&symbol_short!("PENDING"), &new_admin);
+        AdminProposed {
+            admin,
+            pending_admin: new_admin,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Accept a pending admin proposal (pending admin only)
+    pub fn accept_admin(env: &Env) -> Result<(), Error> {
+        let admin = read_admin(env)?;
+        let new_admin = Self::pending_admin(env).ok_or(Error::NoPendingAdmin)?;
+        new_admin.require_auth();
+        extend_instance(env);
+
+        write_admin(env, &new_admin);
+        env.storage().instance().remove(&symbol_short!("PENDING"));
+        SetAdmin { admin, new_admin }.publish(env);
+
+        Ok(())
     }
 
-    /// Mint a new location-based NFT
+    /// Mint a new location-based NFT; returns the token id assigned to it.
+    /// A `soulbound` token can never be transferred (`minter` must be the
+    /// admin or hold the minter role)
     pub fn mint(
         env: &Env,
+        minter: Address,
         to: Address,
-        token_id: u32,
         name: String,
         symbol: String,
         uri: String,
-        latitude: String,
-        longitude: String,
+        latitude: i64,
+        longitude: i64,
         radius: u32,
-    ) -> Result<(), Val> {
-        // Admin check completely removed for testing
-        // TODO: Implement proper admin check when caller identification is available
+        soulbound: bool,
+    ) -> Result<u32, Error> {
+        minter.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_role(env, &minter, Role::Minter)?;
 
-        // Check if token already exists
-        let data_key = DataKey {
-            owner: to.clone(),
-            token_id,
-        };
+        write_token(
+            env, &minter, &to, name, symbol, uri, latitude, longitude, radius, soulbound,
+        )
+    }
 
-        if env.storage().persistent().has(&data_key) {
-            return Err(soroban_sdk::Error::from_contract_error(2).into());
+    /// Mint up to 4 NFTs in one call; returns their token ids. If any token
+    /// is invalid, none are minted (`minter` must be the admin or hold the
+    /// minter role)
+    pub fn mint_batch(
+        env: &Env,
+        minter: Address,
+        requests: Vec<MintRequest>,
+    ) -> Result<Vec<u32>, Error> {
+        minter.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_role(env, &minter, Role::Minter)?;
+        if requests.len() > MAX_MINT_BATCH {
+            return Err(Error::BatchTooLarge);
         }
 
-        // Store token ownership
-        env.storage().persistent().set(&data_key, &true);
+        let mut token_ids = Vec::new(env);
+        for request in requests.iter() {
+            let token_id = write_token(
+                env,
+                &minter,
+                &request.to,
+                request.name,
+                request.symbol,
+                request.uri,
+                request.latitude,
+                request.longitude,
+                request.radius,
+                request.soulbound,
+            )?;
+            token_ids.push_back(token_id);
+        }
 
-        // Store token metadata
-        let metadata = TokenMetadata {
-            name: name.clone(),
-            symbol: symbol.clone(),
-            uri,
-            latitude: latitude.clone(),
-            longitude: longitude.clone(),
-            radius,
-            created_at: env.ledger().timestamp(),
-        };
-        
-        let metadata_key = (token_id, symbol_short!("METADATA"));
-        env.storage().persistent().set(&metadata_key, &metadata);
+        Ok(token_ids)
+    }
 
-        // Store location data
-        let location_data = LocationData {
+    /// Set the ed25519 public key whose position attestations `claim` accepts (admin only)
+    pub fn set_oracle(env: &Env, oracle: BytesN<32>) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+        write_oracle(env, &oracle);
+        Ok(())
+    }
+
+    /// Get the ed25519 public key of the position oracle
+    pub fn oracle(env: &Env) -> Result<BytesN<32>, Error> {
+        read_oracle(env)
+    }
+
+    /// Create a drop of `supply` NFTs claimable near a location between
+    /// `start_time` and `end_time` (admin only); returns the drop id
+    pub fn create_drop(
+        env: &Env,
+        name: String,
+        symbol: String,
+        uri: String,
+        latitude: i64,
+        longitude: i64,
+        radius: u32,
+        supply: u32,
+        start_time: u64,
+        end_time: u64,
+    ) -> Result<u32, Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        check_location(env, latitude, longitude, radius)?;
+        if start_time > end_time {
+            return Err(Error::InvalidTimeWindow);
+        }
+
+        let drop_id = next_drop_id(env);
+        let drop = LocationDrop {
+            name,
+            symbol,
+            uri,
             latitude,
             longitude,
             radius,
+            supply,
+            claimed: 0,
+            start_time,
+            end_time,
         };
-        let location_key = (token_id, symbol_short!("LOCATION"));
-        env.storage().persistent().set(&location_key, &location_data);
+        write_drop(env, drop_id, &drop);
 
-        // Increment total supply
-        let current_supply: u32 = env
-            .storage()
-            .instance()
-            .get(&symbol_short!("SUPPLY"))
-            .unwrap_or(0);
-        env.storage()
-            .instance()
-            .set(&symbol_short!("SUPPLY"), &(current_supply + 1));
+        Ok(drop_id)
+    }
 
-        Ok(())
+    /// Get a drop
+    pub fn get_drop(env: &Env, drop_id: u32) -> Result<LocationDrop, Error> {
+        read_drop(env, drop_id)
+    }
+
+    /// Claim a token from a drop, proving presence with a position
+    /// attestation signed by the oracle; returns the claimed token's id
+    pub fn claim(
+        env: &Env,
+        claimer: Address,
+        drop_id: u32,
+        attestation: PositionAttestation,
+        signature: BytesN<64>,
+    ) -> Result<u32, Error> {
+        claimer.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        let mut drop = read_drop(env, drop_id)?;
+        let now = env.ledger().timestamp();
+        if now < drop.start_time || now > drop.end_time {
+            return Err(Error::DropNotActive);
+        }
+        if drop.claimed >= drop.supply {
+            return Err(Error::DropExhausted);
+        }
+        if has_claimed(env, drop_id, &claimer) {
+            return Err(Error::AlreadyClaimed);
+        }
+
+        // The attestation must be fresh, signed by the oracle and issued for
+        // this contract, claimer and drop.
+        if attestation.contract != env.current_contract_address()
+            || attestation.claimer != claimer
+            || attestation.drop_id != drop_id
+            || attestation.timestamp > now + MAX_CLOCK_SKEW
+        {
+            return Err(Error::InvalidAttestation);
+        }
+        if now.saturating_sub(attestation.timestamp) > MAX_ATTESTATION_AGE {
+            return Err(Error::AttestationExpired);
+        }
+        let oracle = read_oracle(env)?;
+        env.crypto()
+            .ed25519_verify(&oracle, &attestation.clone().to_xdr(env), &signature);
+
+        if !is_valid_position(attestation.latitude, attestation.longitude)
+            || !geo::is_within_radius(
+                drop.latitude,
+                drop.longitude,
+                drop.radius,
+                attestation.latitude,
+                attestation.longitude,
+            )
+        {
+            return Err(Error::OutOfRange);
+        }
+
+        let token_id = write_token(
+            env,
+            &claimer,
+            &claimer,
+            drop.name.clone(),
+            drop.symbol.clone(),
+            drop.uri.clone(),
+            drop.latitude,
+            drop.longitude,
+            drop.radius,
+            false,
+        )?;
+        set_claimed(env, drop_id, &claimer);
+        drop.claimed += 1;
+        write_drop(env, drop_id, &drop);
+
+        Ok(token_id)
     }
 
     /// Transfer an NFT from one address to another
-    pub fn transfer(
+    pub fn transfer(env: &Env, from: Address, to: Address, token_id: u32) -> Result<(), Error> {
+        from.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_owner(env, &from, token_id)?;
+        move_token(env, from, to, token_id)
+    }
+
+    /// Transfer up to 5 NFTs from one address to another. If `from` does
+    /// not own every token, none are transferred
+    pub fn transfer_batch(
+        env: &Env,
+        from: Address,
+        to: Address,
+        token_ids: Vec<u32>,
+    ) -> Result<(), Error> {
+        from.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        if token_ids.len() > MAX_TRANSFER_BATCH {
+            return Err(Error::BatchTooLarge);
+        }
+
+        for token_id in token_ids.iter() {
+            check_owner(env, &from, token_id)?;
+            move_token(env, from.clone(), to.clone(), token_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Transfer an NFT on the owner's behalf (`spender` must be the owner,
+    /// approved for the token, or an approved operator of the owner)
+    pub fn transfer_from(
         env: &Env,
+        spender: Address,
         from: Address,
         to: Address,
         token_id: u32,
-    ) -> Result<(), Val> {
-        let data_key = DataKey {
-            owner: from.clone(),
+    ) -> Result<(), Error> {
+        spender.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_owner(env, &from, token_id)?;
+        if spender != from
+            && read_approval(env, token_id) != Some(spender.clone())
+            && !is_operator(env, from.clone(), spender)
+        {
+            return Err(Error::NotApproved);
+        }
+        move_token(env, from, to, token_id)
+    }
+
+    /// Approve an address to transfer a token until `expiration_ledger`; an
+    /// expiration of 0 revokes the approval (`approver` must be the owner or
+    /// an approved operator of the owner)
+    pub fn approve(
+        env: &Env,
+        approver: Address,
+        approved: Address,
+        token_id: u32,
+        expiration_ledger: u32,
+    ) -> Result<(), Error> {
+        approver.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        let owner = read_owner(env, token_id).ok_or(Error::TokenNotFound)?;
+        if approver != owner && !is_operator(env, owner.clone(), approver) {
+            return Err(Error::NotApproved);
+        }
+
+        write_approval(env, token_id, approved.clone(), expiration_ledger)?;
+        Approve {
+            owner,
             token_id,
-        };
+            approved,
+            expiration_ledger,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Get the address approved to transfer a token, if any
+    pub fn get_approved(env: &Env, token_id: u32) -> Option<Address> {
+        read_approval(env, token_id)
+    }
 
-        // Check if token exists and is owned by 'from'
-        if !env.storage().persistent().has(&data_key) {
-            return Err(soroban_sdk::Error::from_contract_error(3).into());
+    /// Approve an operator to transfer all of the owner's tokens until
+    /// `expiration_ledger`; an expiration of 0 revokes the approval
+    pub fn set_approval_for_all(
+        env: &Env,
+        owner: Address,
+        operator: Address,
+        expiration_ledger: u32,
+    ) -> Result<(), Error> {
+        owner.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        write_operator(env, owner.clone(), operator.clone(), expiration_ledger)?;
+        ApproveForAll {
+            owner,
+            operator,
+            expiration_ledger,
         }
+        .publish(env);
 
-        // Remove from old owner
-        env.storage().persistent().remove(&data_key);
+        Ok(())
+    }
+
+    /// Check if an operator is approved for all of the owner's tokens
+    pub fn is_approved_for_all(env: &Env, owner: Address, operator: Address) -> bool {
+        is_operator(env, owner, operator)
+    }
+
+    /// Burn a token, removing its ownership, metadata and location records
+    /// (`from` must be the token's owner or the admin)
+    pub fn burn(env: &Env, from: Address, token_id: u32) -> Result<(), Error> {
+        from.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        let owner = read_owner(env, token_id).ok_or(Error::TokenNotFound)?;
+        if from != owner && from != read_admin(env)? {
+            return Err(Error::NotOwner);
+        }
 
-        // Add to new owner
-        let new_data_key = DataKey {
-            owner: to.clone(),
+        let location = read_location(env, token_id)?;
+        remove_from_cell(
+            env,
+            grid_cell(location.latitude, location.longitude),
             token_id,
-        };
-        env.storage().persistent().set(&new_data_key, &true);
+        );
+        remove_approval(env, token_id);
+        remove_token(env, &owner, token_id);
+        env.storage()
+            .persistent()
+            .remove(&(token_id, symbol_short!("METADATA")));
+        env.storage()
+            .persistent()
+            .remove(&(token_id, symbol_short!("LOCATION")));
+        env.storage()
+            .persistent()
+            .remove(&(token_id, symbol_short!("FROZEN")));
+        env.storage()
+            .persistent()
+            .remove(&(token_id, symbol_short!("ROYALTY")));
+        env.storage()
+            .persistent()
+            .remove(&(token_id, symbol_short!("SOULBOUND")));
+        remove_polygon(env, token_id);
+
+        let supply = Self::total_supply(env);
+        env.storage()
+            .instance()
+            .set(&symbol_short!("SUPPLY"), &(supply - 1));
+
+        Burn { owner, token_id }.publish(env);
 
         Ok(())
     }
 
-    /// Get the owner of a specific token
-    pub fn owner_of(env: &Env, token_id: u32) -> Result<Address, Val> {
-        // Search through all possible owners (simplified approach)
-        // In a production contract, you'd use a more efficient data structure
-        let total_supply: u32 = env
-            .storage()
+    /// Check whether a token was minted soulbound and cannot be transferred
+    pub fn is_soulbound(env: &Env, token_id: u32) -> bool {
+        is_soulbound(env, token_id)
+    }
+
+    /// Mint every later token soulbound, including drop claims, regardless
+    /// of the `soulbound` argument (admin only). Tokens already minted keep
+    /// their flag
+    pub fn set_default_soulbound(env: &Env, soulbound: bool) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+        env.storage()
             .instance()
-            .get(&symbol_short!("SUPPLY"))
-            .unwrap_or(0);
-
-        for i in 1..=total_supply {
-            let data_key = DataKey {
-                owner: env.current_contract_address(), // Placeholder - would need proper iteration
-                token_id: i,
-            };
-            if env.storage().persistent().has(&data_key) && i == token_id {
-                // Return the actual owner from storage
-                // This is a simplified implementation
-                return Ok(env.current_contract_address());
+            .set(&symbol_short!("SOULBOUND"), &soulbound);
+        Ok(())
+    }
+
+    /// Check whether new tokens are minted soulbound by default
+    pub fn default_soulbound(env: &Env) -> bool {
+        read_default_soulbound(env)
+    }
+
+    /// Get the owner of a specific token
+    pub fn owner_of(env: &Env, token_id: u32) -> Result<Address, Error> {
+        read_owner(env, token_id).ok_or(Error::TokenNotFound)
+    }
+
+    /// Get token metadata; an empty name or symbol is filled in from the
+    /// collection's
+    pub fn get_metadata(env: &Env, token_id: u32) -> Result<TokenMetadata, Error> {
+        let mut metadata = read_metadata(env, token_id)?;
+        if metadata.name.is_empty() {
+            metadata.name = Self::name(env)?;
+        }
+        if metadata.symbol.is_empty() {
+            metadata.symbol = Self::symbol(env)?;
+        }
+        Ok(metadata)
+    }
+
+    /// Get a token's URI: its own uri if set, otherwise the collection's
+    /// base URI followed by the token id (empty if neither is set)
+    pub fn token_uri(env: &Env, token_id: u32) -> Result<String, Error> {
+        let metadata = read_metadata(env, token_id)?;
+        if !metadata.uri.is_empty() {
+            return Ok(metadata.uri);
+        }
+        let base_uri = read_collection_metadata(env).base_uri;
+        if base_uri.is_empty() {
+            return Ok(base_uri);
+        }
+        Ok(compose_token_uri(env, &base_uri, token_id))
+    }
+
+    /// Set the collection's description, image, external URL and base URI
+    /// (admin only)
+    pub fn set_collection_metadata(env: &Env, metadata: CollectionMetadata) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+
+        write_collection_metadata(env, &metadata)?;
+        CollectionMetadataUpdated {
+            description: metadata.description,
+            image: metadata.image,
+            external_url: metadata.external_url,
+            base_uri: metadata.base_uri,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Get the collection's metadata
+    pub fn collection_metadata(env: &Env) -> CollectionMetadata {
+        read_collection_metadata(env)
+    }
+
+    /// Update a token's name, symbol and uri, keeping its location and
+    /// `created_at` (`caller` must be the token's owner or the admin)
+    pub fn update_metadata(
+        env: &Env,
+        caller: Address,
+        token_id: u32,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        let owner = read_owner(env, token_id).ok_or(Error::TokenNotFound)?;
+        if caller != owner && caller != read_admin(env)? {
+            return Err(Error::NotOwner);
+        }
+        if is_metadata_frozen(env, token_id) {
+            return Err(Error::MetadataFrozen);
+        }
+
+        let mut metadata = read_metadata(env, token_id)?;
+        metadata.name = name.clone();
+        metadata.symbol = symbol.clone();
+        metadata.uri = uri.clone();
+        metadata.updated_at = env.ledger().timestamp();
+        write_metadata(env, token_id, &metadata);
+
+        MetadataUpdated {
+            token_id,
+            name,
+            symbol,
+            uri,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Permanently freeze a token's metadata, or with no `token_id` the
+    /// metadata of every token (`caller` must be the token's owner or the
+    /// admin; only the admin can freeze the whole collection)
+    pub fn freeze_metadata(env: &Env, caller: Address, token_id: Option<u32>) -> Result<(), Error> {
+        caller.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        match token_id {
+            Some(token_id) => {
+                let owner = read_owner(env, token_id).ok_or(Error::TokenNotFound)?;
+                if caller != owner && caller != read_admin(env)? {
+                    return Err(Error::NotOwner);
+                }
+                let frozen_key = (token_id, symbol_short!("FROZEN"));
+                env.storage().persistent().set(&frozen_key, &true);
+                env.storage().persistent().extend_ttl(
+                    &frozen_key,
+                    TOKEN_LIFETIME_THRESHOLD,
+                    TOKEN_BUMP_AMOUNT,
+                );
+            }
+            None => {
+                if caller != read_admin(env)? {
+                    return Err(Error::NotOwner);
+                }
+                env.storage()
+                    .instance()
+                    .set(&symbol_short!("FROZEN"), &true);
             }
         }
-        
-        Err(soroban_sdk::Error::from_contract_error(4).into())
+
+        MetadataFrozen { caller, token_id }.publish(env);
+
+        Ok(())
     }
 
-    /// Get token metadata
-    pub fn get_metadata(env: &Env, token_id: u32) -> Result<TokenMetadata, Val> {
-        let metadata_key = (token_id, symbol_short!("METADATA"));
-        let metadata: TokenMetadata = env
-            .storage()
-            .persistent()
-            .get(&metadata_key)
-            .unwrap();
-        Ok(metadata)
+    /// Check whether a token's metadata can no longer be updated
+    pub fn metadata_frozen(env: &Env, token_id: u32) -> bool {
+        is_metadata_frozen(env, token_id)
+    }
+
+    /// Set the royalty paid on resales of tokens without their own royalty
+    /// (admin only)
+    pub fn set_default_royalty(
+        env: &Env,
+        receiver: Address,
+        basis_points: u32,
+    ) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+
+        let royalty = Royalty {
+            receiver: receiver.clone(),
+            basis_points,
+        };
+        write_default_royalty(env, &royalty)?;
+        RoyaltySet {
+            token_id: None,
+            receiver,
+            basis_points,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Set the royalty paid on resales of a token, overriding the default
+    /// (admin only)
+    pub fn set_token_royalty(
+        env: &Env,
+        token_id: u32,
+        receiver: Address,
+        basis_points: u32,
+    ) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        if !has_token(env, token_id) {
+            return Err(Error::TokenNotFound);
+        }
+        let royalty = Royalty {
+            receiver: receiver.clone(),
+            basis_points,
+        };
+        write_token_royalty(env, token_id, &royalty)?;
+        RoyaltySet {
+            token_id: Some(token_id),
+            receiver,
+            basis_points,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Get the royalty receiver and the amount owed to them on a sale of a
+    /// token for `sale_price`
+    pub fn royalty_info(
+        env: &Env,
+        token_id: u32,
+        sale_price: i128,
+    ) -> Result<(Address, i128), Error> {
+        if !has_token(env, token_id) {
+            return Err(Error::TokenNotFound);
+        }
+        if sale_price < 0 {
+            return Err(Error::InvalidRoyalty);
+        }
+        let royalty = read_royalty(env, token_id).ok_or(Error::RoyaltyNotSet)?;
+        let amount = royalty_amount(sale_price, royalty.basis_points);
+        Ok((royalty.receiver, amount))
     }
 
     /// Get location data for a token
-    pub fn get_location(env: &Env, token_id: u32) -> Result<LocationData, Val> {
-        let location_key = (token_id, symbol_short!("LOCATION"));
-        let location: LocationData = env
-            .storage()
-            .persistent()
-            .get(&location_key)
-            .unwrap();
-        Ok(location)
+    pub fn get_location(env: &Env, token_id: u32) -> Result<LocationData, Error> {
+        read_location(env, token_id)
+    }
+
+    /// Check whether a token is inside its validity window
+    pub fn is_active(env: &Env, token_id: u32) -> Result<bool, Error> {
+        let location = read_location(env, token_id)?;
+        Ok(is_location_active(env, &location))
+    }
+
+    /// Set the ledger timestamps between which a token is valid; `None`
+    /// leaves that end of the window open (`caller` must be the admin or
+    /// hold the location-updater role)
+    pub fn set_validity(
+        env: &Env,
+        caller: Address,
+        token_id: u32,
+        valid_from: Option<u64>,
+        valid_until: Option<u64>,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_role(env, &caller, Role::LocationUpdater)?;
+
+        let mut location = read_location(env, token_id)?;
+        if let (Some(from), Some(until)) = (valid_from, valid_until) {
+            if from > until {
+                return Err(Error::InvalidTimeWindow);
+            }
+        }
+        location.valid_from = valid_from;
+        location.valid_until = valid_until;
+        write_location(env, token_id, &location);
+
+        ValidityUpdated {
+            token_id,
+            valid_from,
+            valid_until,
+        }
+        .publish(env);
+
+        Ok(())
+    }
+
+    /// Check whether a microdegree position falls within a token's radius;
+    /// fails for tokens outside their validity window
+    pub fn is_within_radius(
+        env: &Env,
+        token_id: u32,
+        latitude: i64,
+        longitude: i64,
+    ) -> Result<bool, Error> {
+        if !is_valid_position(latitude, longitude) {
+            return Err(Error::InvalidCoordinate);
+        }
+        let location = read_location(env, token_id)?;
+        if !is_location_active(env, &location) {
+            return Err(Error::TokenNotActive);
+        }
+        Ok(geo::is_within_radius(
+            location.latitude,
+            location.longitude,
+            location.radius,
+            latitude,
+            longitude,
+        ))
+    }
+
+    /// Set a token's polygon geofence from up to 32 (latitude, longitude)
+    /// vertices in microdegrees; an empty list removes it (`caller` must be
+    /// the admin or hold the location-updater role)
+    pub fn set_polygon(
+        env: &Env,
+        caller: Address,
+        token_id: u32,
+        vertices: Vec<(i64, i64)>,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_role(env, &caller, Role::LocationUpdater)?;
+
+        if !has_token(env, token_id) {
+            return Err(Error::TokenNotFound);
+        }
+        if vertices.is_empty() {
+            remove_polygon(env, token_id);
+        } else {
+            write_polygon(env, token_id, &vertices)?;
+        }
+
+        PolygonUpdated { token_id, vertices }.publish(env);
+
+        Ok(())
+    }
+
+    /// Get a token's polygon geofence vertices (empty if it has none)
+    pub fn get_polygon(env: &Env, token_id: u32) -> Result<Vec<(i64, i64)>, Error> {
+        if !has_token(env, token_id) {
+            return Err(Error::TokenNotFound);
+        }
+        Ok(read_polygon(env, token_id).unwrap_or_else(|| Vec::new(env)))
+    }
+
+    /// Check whether a microdegree position falls inside, or on the edge of,
+    /// a token's polygon geofence; fails for tokens outside their validity
+    /// window
+    pub fn is_within_polygon(
+        env: &Env,
+        token_id: u32,
+        latitude: i64,
+        longitude: i64,
+    ) -> Result<bool, Error> {
+        if !is_valid_position(latitude, longitude) {
+            return Err(Error::InvalidCoordinate);
+        }
+        let location = read_location(env, token_id)?;
+        if !is_location_active(env, &location) {
+            return Err(Error::TokenNotActive);
+        }
+        let vertices = read_polygon(env, token_id).ok_or(Error::PolygonNotSet)?;
+        Ok(geo::is_within_polygon(&vertices, latitude, longitude))
+    }
+
+    /// Import token `legacy_id` from a first-release LocationNFT contract at
+    /// `legacy` under a new id, which is returned, converting its string
+    /// coordinates to microdegrees (admin only). `owner` must own the token
+    /// there; the legacy contract is only read
+    pub fn import_legacy(
+        env: &Env,
+        legacy: Address,
+        owner: Address,
+        legacy_id: u32,
+    ) -> Result<u32, Error> {
+        let admin = read_admin(env)?;
+        admin.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+
+        if read_imported(env, &legacy, legacy_id).is_some() {
+            return Err(Error::TokenExists);
+        }
+        let (metadata, location) = read_legacy_token(env, &legacy, &owner, legacy_id)?;
+        check_location(env, location.latitude, location.longitude, location.radius)?;
+        let token_id = next_token_id(env);
+        store_token(env, &admin, &owner, token_id, &metadata, &location, false);
+        write_imported(env, &legacy, legacy_id, token_id);
+        Imported {
+            legacy,
+            legacy_id,
+            token_id,
+        }
+        .publish(env);
+
+        Ok(token_id)
+    }
+
+    /// Get the id a first-release token was imported under, if it was
+    pub fn imported_id(env: &Env, legacy: Address, legacy_id: u32) -> Option<u32> {
+        read_imported(env, &legacy, legacy_id)
+    }
+
+    /// Extend the TTL of a token's storage entries so they are not archived;
+    /// anyone can call this. Its location history records are extended with
+    /// `bump_history`
+    pub fn bump_token(env: &Env, token_id: u32) -> Result<(), Error> {
+        if !has_token(env, token_id) {
+            return Err(Error::TokenNotFound);
+        }
+        extend_instance(env);
+        extend_token_state(env, token_id)?;
+        extend_history_len(env, token_id);
+        Ok(())
+    }
+
+    /// Extend the TTL of up to `limit` (at most 50) of a token's location
+    /// records, starting at position `start`, and of its history length;
+    /// anyone can call this, also for burned tokens
+    pub fn bump_history(env: &Env, token_id: u32, start: u32, limit: u32) {
+        extend_instance(env);
+        extend_history_len(env, token_id);
+        extend_history(env, token_id, start, limit.min(MAX_PAGE_SIZE));
+    }
+
+    /// Get the largest radius accepted by `mint` and `update_location`
+    pub fn max_radius(env: &Env) -> u32 {
+        read_max_radius(env)
+    }
+
+    /// Set the largest radius accepted by `mint` and `update_location` (admin only)
+    pub fn set_max_radius(env: &Env, max_radius: u32) -> Result<(), Error> {
+        read_admin(env)?.require_auth();
+        extend_instance(env);
+
+        env.storage()
+            .instance()
+            .set(&symbol_short!("MAXRADIUS"), &max_radius);
+
+        Ok(())
     }
 
     /// Get contract name
-    pub fn name(env: &Env) -> String {
+    pub fn name(env: &Env) -> Result<String, Error> {
         env.storage()
             .instance()
             .get(&symbol_short!("NAME"))
-            .unwrap()
+            .ok_or(Error::NotInitialized)
     }
 
     /// Get contract symbol
-    pub fn symbol(env: &Env) -> String {
+    pub fn symbol(env: &Env) -> Result<String, Error> {
         env.storage()
             .instance()
             .get(&symbol_short!("SYMBOL"))
-            .unwrap()
+            .ok_or(Error::NotInitialized)
     }
 
     /// Get total supply
@@ -219,52 +1383,94 @@ impl LocationNFT {
 
     /// Check if an address owns a specific token
     pub fn is_owner(env: &Env, owner: Address, token_id: u32) -> bool {
-        let data_key = DataKey {
-            owner,
-            token_id,
-        };
-        env.storage().persistent().has(&data_key)
+        read_owner(env, token_id) == Some(owner)
     }
 
-    /// Get all tokens owned by an address (simplified - returns count)
+    /// Get the number of tokens owned by an address
     pub fn balance_of(env: &Env, owner: Address) -> u32 {
-        let total_supply: u32 = env
-            .storage()
-            .instance()
-            .get(&symbol_short!("SUPPLY"))
-            .unwrap_or(0);
-
-        let mut count = 0;
-        for i in 1..=total_supply {
-            let data_key = DataKey {
-                owner: owner.clone(),
-                token_id: i,
-            };
-            if env.storage().persistent().has(&data_key) {
-                count += 1;
-            }
+        read_balance(env, &owner)
+    }
+
+    /// Get up to `limit` (at most 50) token ids owned by an address,
+    /// starting at position `start` in the owner's token list
+    pub fn tokens_of_owner(env: &Env, owner: Address, start: u32, limit: u32) -> Vec<u32> {
+        read_tokens(env, &owner, start, limit.min(MAX_PAGE_SIZE))
+    }
+
+    /// Get the grid cell containing a microdegree position, identified by its
+    /// south-west corner; cells are 0.001 degrees on each side
+    pub fn cell_id(latitude: i64, longitude: i64) -> Result<(i64, i64), Error> {
+        if !is_valid_position(latitude, longitude) {
+            return Err(Error::InvalidCoordinate);
         }
-        count
+        Ok(grid_cell(latitude, longitude))
+    }
+
+    /// Get up to `limit` (at most 50) of a token's location records, oldest
+    /// first, starting at position `start`. Records outlive burned tokens
+    pub fn location_history(
+        env: &Env,
+        token_id: u32,
+        start: u32,
+        limit: u32,
+    ) -> Vec<LocationRecord> {
+        read_history(env, token_id, start, limit.min(MAX_PAGE_SIZE))
+    }
+
+    /// Get up to `limit` (at most 50) ids of the tokens centered in a grid
+    /// cell, starting at position `start` in the cell's token list
+    pub fn tokens_in_cell(env: &Env, cell_id: (i64, i64), start: u32, limit: u32) -> Vec<u32> {
+        read_cell_tokens(env, cell_id, start, limit.min(MAX_PAGE_SIZE))
     }
 
-    /// Update location data for a token (admin only)
+    /// Update location data for a token; the previous location is kept in
+    /// the token's history (`caller` must be the admin or hold the
+    /// location-updater role)
     pub fn update_location(
         env: &Env,
+        caller: Address,
         token_id: u32,
-        latitude: String,
-        longitude: String,
+        latitude: i64,
+        longitude: i64,
         radius: u32,
-    ) -> Result<(), Val> {
-        // Admin check temporarily removed for testing
-        // TODO: Implement proper admin check when caller identification is available
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        extend_instance(env);
+        check_not_paused(env)?;
+        check_role(env, &caller, Role::LocationUpdater)?;
+
+        if !has_token(env, token_id) {
+            return Err(Error::TokenNotFound);
+        }
+        check_location(env, latitude, longitude, radius)?;
+
+        // Move the token to its new cell.
+        let previous = read_location(env, token_id)?;
+        let previous_cell = grid_cell(previous.latitude, previous.longitude);
+        let cell = grid_cell(latitude, longitude);
+        if cell != previous_cell {
+            remove_from_cell(env, previous_cell, token_id);
+            add_to_cell(env, cell, token_id);
+        }
 
         let location_data = LocationData {
             latitude,
             longitude,
             radius,
+            valid_from: previous.valid_from,
+            valid_until: previous.valid_until,
         };
-        let location_key = (token_id, symbol_short!("LOCATION"));
-        env.storage().persistent().set(&location_key, &location_data);
+        write_location(env, token_id, &location_data);
+        append_history(env, token_id, latitude, longitude, radius, &caller);
+
+        LocationUpdated {
+            token_id,
+            latitude,
+            longitude,
+            radius,
+            updater: caller,
+        }
+        .publish(env);
 
         Ok(())
     }
@@ -0,0 +1,133 @@
+//! Token ownership records and the per-owner token index.
+//!
+//! Each owner's tokens are stored as a dense list of `(owner, index)` entries
+//! with a reverse `token_id -> index` entry, so adding and removing a token
+//! and reading a balance are all constant time. Removal moves the owner's
+//! last token into the freed slot, so list order is not stable.
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use soroban_sdk::{symbol_short, Address, Env, Symbol, Vec};
+
+/// Instance key of the last token id issued.
+#[cfg(not(feature = "next-version"))]
+pub(crate) const TOKEN_ID_KEY: Symbol = symbol_short!("TOKEN_ID");
+/// The `next-version` build keeps the counter under a new key; its migration
+/// moves it there.
+#[cfg(feature = "next-version")]
+pub(crate) const TOKEN_ID_KEY: Symbol = symbol_short!("LAST_ID");
+
+pub fn read_owner(env: &Env, token_id: u32) -> Option<Address> {
+    let owner_key = (token_id, symbol_short!("OWNER"));
+    env.storage().persistent().get(&owner_key)
+}
+
+pub fn has_token(env: &Env, token_id: u32) -> bool {
+    let owner_key = (token_id, symbol_short!("OWNER"));
+    env.storage().persistent().has(&owner_key)
+}
+
+/// Allocates the next token id. Ids are never reused.
+pub fn next_token_id(env: &Env) -> u32 {
+    let token_id = env
+        .storage()
+        .instance()
+        .get::<_, u32>(&TOKEN_ID_KEY)
+        .unwrap_or(0)
+        + 1;
+    env.storage().instance().set(&TOKEN_ID_KEY, &token_id);
+    token_id
+}
+
+pub fn read_balance(env: &Env, owner: &Address) -> u32 {
+    let balance_key = (owner.clone(), symbol_short!("BALANCE"));
+    env.storage().persistent().get(&balance_key).unwrap_or(0)
+}
+
+fn write_balance(env: &Env, owner: &Address, balance: u32) {
+    let balance_key = (owner.clone(), symbol_short!("BALANCE"));
+    if balance == 0 {
+        env.storage().persistent().remove(&balance_key);
+    } else {
+        env.storage().persistent().set(&balance_key, &balance);
+        env.storage().persistent().extend_ttl(
+            &balance_key,
+            TOKEN_LIFETIME_THRESHOLD,
+            TOKEN_BUMP_AMOUNT,
+        );
+    }
+}
+
+fn write_owned_token(env: &Env, owner: &Address, index: u32, token_id: u32) {
+    let owned_key = (owner.clone(), index, symbol_short!("OWNED"));
+    env.storage().persistent().set(&owned_key, &token_id);
+    env.storage()
+        .persistent()
+        .extend_ttl(&owned_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let index_key = (token_id, symbol_short!("OWNED_IDX"));
+    env.storage().persistent().set(&index_key, &index);
+    env.storage()
+        .persistent()
+        .extend_ttl(&index_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+/// Records `owner` as the owner of `token_id` and appends it to their index.
+pub fn add_token(env: &Env, owner: &Address, token_id: u32) {
+    let owner_key = (token_id, symbol_short!("OWNER"));
+    env.storage().persistent().set(&owner_key, owner);
+    env.storage()
+        .persistent()
+        .extend_ttl(&owner_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+
+    let balance = read_balance(env, owner);
+    write_owned_token(env, owner, balance, token_id);
+    write_balance(env, owner, balance + 1);
+}
+
+/// Removes `token_id` from `owner`'s index and clears its owner record.
+pub fn remove_token(env: &Env, owner: &Address, token_id: u32) {
+    let owner_key = (token_id, symbol_short!("OWNER"));
+    env.storage().persistent().remove(&owner_key);
+
+    let index_key = (token_id, symbol_short!("OWNED_IDX"));
+    let index: u32 = env.storage().persistent().get(&index_key).unwrap();
+    env.storage().persistent().remove(&index_key);
+
+    let last = read_balance(env, owner) - 1;
+    if index != last {
+        let last_key = (owner.clone(), last, symbol_short!("OWNED"));
+        let last_token: u32 = env.storage().persistent().get(&last_key).unwrap();
+        write_owned_token(env, owner, index, last_token);
+    }
+    let last_key = (owner.clone(), last, symbol_short!("OWNED"));
+    env.storage().persistent().remove(&last_key);
+    write_balance(env, owner, last);
+}
+
+/// Extends the TTL of `token_id`'s owner record, its slot in the owner's
+/// index and the owner's balance.
+pub fn extend_ownership(env: &Env, token_id: u32) {
+    let storage = env.storage().persistent();
+    let owner_key = (token_id, symbol_short!("OWNER"));
+    let Some(owner) = storage.get::<_, Address>(&owner_key) else {
+        return;
+    };
+    storage.extend_ttl(&owner_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+
+    let index_key = (token_id, symbol_short!("OWNED_IDX"));
+    let index: u32 = storage.get(&index_key).unwrap();
+    storage.extend_ttl(&index_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let owned_key = (owner.clone(), index, symbol_short!("OWNED"));
+    storage.extend_ttl(&owned_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let balance_key = (owner, symbol_short!("BALANCE"));
+    storage.extend_ttl(&balance_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+/// Returns up to `limit` of `owner`'s token ids, starting at index `start`.
+pub fn read_tokens(env: &Env, owner: &Address, start: u32, limit: u32) -> Vec<u32> {
+    let end = read_balance(env, owner).min(start.saturating_add(limit));
+    let mut tokens = Vec::new(env);
+    for index in start..end {
+        let owned_key = (owner.clone(), index, symbol_short!("OWNED"));
+        tokens.push_back(env.storage().persistent().get(&owned_key).unwrap());
+    }
+    tokens
+}
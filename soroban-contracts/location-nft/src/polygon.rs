@@ -0,0 +1,43 @@
+//! Polygon geofences: a token may carry a list of (latitude, longitude)
+//! vertices in microdegrees alongside its center and radius.
+use crate::geo::is_valid_position;
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use crate::Error;
+use soroban_sdk::{symbol_short, Env, Vec};
+
+/// Fewest vertices that enclose an area.
+const MIN_POLYGON_VERTICES: u32 = 3;
+/// Most vertices a polygon may have, which bounds the cost of storing it and
+/// of `is_within_polygon`.
+pub(crate) const MAX_POLYGON_VERTICES: u32 = 32;
+
+pub fn read_polygon(env: &Env, token_id: u32) -> Option<Vec<(i64, i64)>> {
+    env.storage()
+        .persistent()
+        .get(&(token_id, symbol_short!("POLYGON")))
+}
+
+pub fn write_polygon(env: &Env, token_id: u32, vertices: &Vec<(i64, i64)>) -> Result<(), Error> {
+    if vertices.len() < MIN_POLYGON_VERTICES || vertices.len() > MAX_POLYGON_VERTICES {
+        return Err(Error::InvalidPolygon);
+    }
+    for (latitude, longitude) in vertices.iter() {
+        if !is_valid_position(latitude, longitude) {
+            return Err(Error::InvalidCoordinate);
+        }
+    }
+    let polygon_key = (token_id, symbol_short!("POLYGON"));
+    env.storage().persistent().set(&polygon_key, vertices);
+    env.storage().persistent().extend_ttl(
+        &polygon_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+    Ok(())
+}
+
+pub fn remove_polygon(env: &Env, token_id: u32) {
+    env.storage()
+        .persistent()
+        .remove(&(token_id, symbol_short!("POLYGON")));
+}
@@ -0,0 +1,58 @@
+//! Resale royalties, in the style of EIP-2981: a collection-wide default that
+//! individual tokens can override.
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use crate::Error;
+use soroban_sdk::{contracttype, symbol_short, Address, Env};
+
+/// Basis points in 100%.
+const MAX_BASIS_POINTS: u32 = 10_000;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Royalty {
+    pub receiver: Address,
+    pub basis_points: u32,
+}
+
+fn check_royalty(royalty: &Royalty) -> Result<(), Error> {
+    if royalty.basis_points > MAX_BASIS_POINTS {
+        return Err(Error::InvalidRoyalty);
+    }
+    Ok(())
+}
+
+pub fn write_default_royalty(env: &Env, royalty: &Royalty) -> Result<(), Error> {
+    check_royalty(royalty)?;
+    env.storage()
+        .instance()
+        .set(&symbol_short!("ROYALTY"), royalty);
+    Ok(())
+}
+
+pub fn write_token_royalty(env: &Env, token_id: u32, royalty: &Royalty) -> Result<(), Error> {
+    check_royalty(royalty)?;
+    let royalty_key = (token_id, symbol_short!("ROYALTY"));
+    env.storage().persistent().set(&royalty_key, royalty);
+    env.storage().persistent().extend_ttl(
+        &royalty_key,
+        TOKEN_LIFETIME_THRESHOLD,
+        TOKEN_BUMP_AMOUNT,
+    );
+    Ok(())
+}
+
+/// Returns `token_id`'s override, or else the collection default.
+pub fn read_royalty(env: &Env, token_id: u32) -> Option<Royalty> {
+    let royalty_key = (token_id, symbol_short!("ROYALTY"));
+    env.storage()
+        .persistent()
+        .get(&royalty_key)
+        .or_else(|| env.storage().instance().get(&symbol_short!("ROYALTY")))
+}
+
+/// Returns `basis_points` of `sale_price`, rounded down, without overflowing.
+pub fn royalty_amount(sale_price: i128, basis_points: u32) -> i128 {
+    let basis_points = basis_points as i128;
+    let scale = MAX_BASIS_POINTS as i128;
+    sale_price / scale * basis_points + sale_price % scale * basis_points / scale
+}
@@ -0,0 +1,87 @@
+//! Grid-cell index of token locations.
+//!
+//! Tokens are listed under the cell containing their center (see
+//! `geo::grid_cell`). Like the per-owner index in `ownership`, each cell is a
+//! dense list of `(cell, index)` entries with a reverse `token_id -> index`
+//! entry, and removal moves the cell's last token into the freed slot.
+use crate::storage_types::{TOKEN_BUMP_AMOUNT, TOKEN_LIFETIME_THRESHOLD};
+use soroban_sdk::{symbol_short, Env, Vec};
+
+fn read_cell_len(env: &Env, cell: (i64, i64)) -> u32 {
+    let len_key = (cell.0, cell.1, symbol_short!("CELL_LEN"));
+    env.storage().persistent().get(&len_key).unwrap_or(0)
+}
+
+fn write_cell_len(env: &Env, cell: (i64, i64), len: u32) {
+    let len_key = (cell.0, cell.1, symbol_short!("CELL_LEN"));
+    if len == 0 {
+        env.storage().persistent().remove(&len_key);
+    } else {
+        env.storage().persistent().set(&len_key, &len);
+        env.storage().persistent().extend_ttl(
+            &len_key,
+            TOKEN_LIFETIME_THRESHOLD,
+            TOKEN_BUMP_AMOUNT,
+        );
+    }
+}
+
+fn write_cell_token(env: &Env, cell: (i64, i64), index: u32, token_id: u32) {
+    let cell_key = (cell.0, cell.1, index, symbol_short!("CELL"));
+    env.storage().persistent().set(&cell_key, &token_id);
+    env.storage()
+        .persistent()
+        .extend_ttl(&cell_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let index_key = (token_id, symbol_short!("CELL_IDX"));
+    env.storage().persistent().set(&index_key, &index);
+    env.storage()
+        .persistent()
+        .extend_ttl(&index_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+/// Appends `token_id` to `cell`'s list.
+pub fn add_to_cell(env: &Env, cell: (i64, i64), token_id: u32) {
+    let len = read_cell_len(env, cell);
+    write_cell_token(env, cell, len, token_id);
+    write_cell_len(env, cell, len + 1);
+}
+
+/// Removes `token_id` from `cell`'s list.
+pub fn remove_from_cell(env: &Env, cell: (i64, i64), token_id: u32) {
+    let index_key = (token_id, symbol_short!("CELL_IDX"));
+    let index: u32 = env.storage().persistent().get(&index_key).unwrap();
+    env.storage().persistent().remove(&index_key);
+
+    let last = read_cell_len(env, cell) - 1;
+    if index != last {
+        let last_key = (cell.0, cell.1, last, symbol_short!("CELL"));
+        let last_token: u32 = env.storage().persistent().get(&last_key).unwrap();
+        write_cell_token(env, cell, index, last_token);
+    }
+    let last_key = (cell.0, cell.1, last, symbol_short!("CELL"));
+    env.storage().persistent().remove(&last_key);
+    write_cell_len(env, cell, last);
+}
+
+/// Extends the TTL of `token_id`'s index entries under `cell`.
+pub fn extend_cell_entries(env: &Env, cell: (i64, i64), token_id: u32) {
+    let storage = env.storage().persistent();
+    let index_key = (token_id, symbol_short!("CELL_IDX"));
+    let index: u32 = storage.get(&index_key).unwrap();
+    storage.extend_ttl(&index_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let cell_key = (cell.0, cell.1, index, symbol_short!("CELL"));
+    storage.extend_ttl(&cell_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+    let len_key = (cell.0, cell.1, symbol_short!("CELL_LEN"));
+    storage.extend_ttl(&len_key, TOKEN_LIFETIME_THRESHOLD, TOKEN_BUMP_AMOUNT);
+}
+
+/// Returns up to `limit` of the token ids in `cell`, starting at index `start`.
+pub fn read_cell_tokens(env: &Env, cell: (i64, i64), start: u32, limit: u32) -> Vec<u32> {
+    let end = read_cell_len(env, cell).min(start.saturating_add(limit));
+    let mut tokens = Vec::new(env);
+    for index in start..end {
+        let cell_key = (cell.0, cell.1, index, symbol_short!("CELL"));
+        tokens.push_back(env.storage().persistent().get(&cell_key).unwrap());
+    }
+    tokens
+}
@@ -0,0 +1,14 @@
+//! Storage TTL policy.
+//!
+//! Entries are extended to the bump amount whenever their TTL falls below the
+//! lifetime threshold, so an active contract extends each entry at most about
+//! once a day. Instance entries are extended by every state-changing call, and
+//! token and drop entries whenever they are written. `bump_token` extends a
+//! token's entries without changing them. Role grants are extended when
+//! granted and whenever they are used.
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+pub(crate) const TOKEN_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const TOKEN_LIFETIME_THRESHOLD: u32 = TOKEN_BUMP_AMOUNT - DAY_IN_LEDGERS;
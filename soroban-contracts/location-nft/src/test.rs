@@ -0,0 +1,3931 @@
+#![cfg(test)]
+extern crate std;
+
+use crate::{
+    batch::{MAX_MINT_BATCH, MAX_TRANSFER_BATCH, MINT_FOOTPRINT, TRANSFER_FOOTPRINT},
+    geo::{distance_meters, is_within_polygon, parse_microdegrees},
+    ownership::TOKEN_ID_KEY,
+    polygon::MAX_POLYGON_VERTICES,
+    storage_types::{DAY_IN_LEDGERS, INSTANCE_BUMP_AMOUNT, TOKEN_BUMP_AMOUNT},
+    upgrade::{migrate_step, CONTRACT_VERSION},
+    CollectionMetadata, Error, LocationData, LocationNFT, LocationNFTClient, LocationRecord,
+    MintRequest, PositionAttestation, Role, TokenMetadata,
+};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    map, symbol_short,
+    testutils::{
+        storage::{Instance as _, Persistent as _},
+        Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger, MockAuth,
+        MockAuthInvoke,
+    },
+    vec,
+    xdr::ToXdr,
+    Address, BytesN, Env, IntoVal, Map, String, Symbol, Val,
+};
+
+// The first LocationNFT release, as deployed by the frontend.
+mod location_nft_v1 {
+    soroban_sdk::contractimport!(
+        file = "../../frontend/public/soroban-contracts/location_nft.wasm"
+    );
+}
+
+// This contract's wasm build and its `next-version` build, one storage
+// version ahead, made by `./build.sh --test-wasms`.
+#[cfg(feature = "upgrade-tests")]
+mod location_nft_current {
+    soroban_sdk::contractimport!(file = "target/wasm32v1-none/release/location_nft.wasm");
+}
+#[cfg(feature = "upgrade-tests")]
+mod location_nft_next {
+    soroban_sdk::contractimport!(file = "target/next/wasm32v1-none/release/location_nft.wasm");
+}
+
+fn create_nft<'a>(e: &Env, admin: &Address) -> LocationNFTClient<'a> {
+    let contract_id = e.register(LocationNFT, ());
+    let nft = LocationNFTClient::new(e, &contract_id);
+    nft.initialize(
+        admin,
+        &String::from_str(e, "StellarGeoLinkNFT"),
+        &String::from_str(e, "SGL"),
+    );
+    nft
+}
+
+fn mint(e: &Env, nft: &LocationNFTClient, to: &Address) -> u32 {
+    nft.mint(
+        &nft.admin(),
+        to,
+        &String::from_str(e, "Central Park NFT"),
+        &String::from_str(e, "CP"),
+        &String::from_str(e, "https://example.com/metadata.json"),
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    )
+}
+
+#[test]
+fn test_owner_of_follows_transfers() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let user3 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(nft.owner_of(&1), user1);
+    assert!(nft.is_owner(&user1, &1));
+    assert_eq!(nft.balance_of(&user1), 1);
+
+    nft.transfer(&user1, &user2, &1);
+    assert_eq!(nft.owner_of(&1), user2);
+    assert!(!nft.is_owner(&user1, &1));
+    assert!(nft.is_owner(&user2, &1));
+    assert_eq!(nft.balance_of(&user1), 0);
+    assert_eq!(nft.balance_of(&user2), 1);
+
+    nft.transfer(&user2, &user3, &1);
+    assert_eq!(nft.owner_of(&1), user3);
+    assert!(!nft.is_owner(&user2, &1));
+    assert!(nft.is_owner(&user3, &1));
+    assert_eq!(nft.balance_of(&user2), 0);
+    assert_eq!(nft.balance_of(&user3), 1);
+    assert_eq!(nft.total_supply(), 1);
+}
+
+#[test]
+fn test_owner_of_unknown_token() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert_eq!(nft.try_owner_of(&1), Err(Ok(Error::TokenNotFound)));
+}
+
+#[test]
+fn test_mint_assigns_sequential_ids() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // Ids are unique across owners.
+    assert_eq!(mint(&e, &nft, &user1), 1);
+    assert_eq!(mint(&e, &nft, &user2), 2);
+    assert_eq!(nft.owner_of(&1), user1);
+    assert_eq!(nft.owner_of(&2), user2);
+
+    // Burned ids are not reused.
+    nft.burn(&user2, &2);
+    assert_eq!(mint(&e, &nft, &user2), 3);
+
+    // Tokens imported from the first release take the next id whatever
+    // their legacy id, and burning one does not free its id.
+    let legacy = create_legacy_nft(&e);
+    legacy_mint(&e, &legacy, &user1, 10, "40.7829", "-73.9654");
+    legacy_mint(&e, &legacy, &user1, 1, "40.7829", "-73.9654");
+    assert_eq!(nft.import_legacy(&legacy.address, &user1, &10), 4);
+    nft.burn(&user1, &4);
+    assert_eq!(mint(&e, &nft, &user2), 5);
+    assert_eq!(nft.location_history(&5, &0, &10).len(), 1);
+    assert_eq!(nft.import_legacy(&legacy.address, &user1, &1), 6);
+    assert_eq!(nft.owner_of(&5), user2);
+    assert_eq!(nft.owner_of(&6), user1);
+    assert_eq!(nft.imported_id(&legacy.address, &10), Some(4));
+    assert_eq!(nft.imported_id(&legacy.address, &1), Some(6));
+    assert_eq!(nft.imported_id(&legacy.address, &2), None);
+}
+
+#[test]
+fn test_transfer_by_non_owner_rejected() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(
+        nft.try_transfer(&user2, &user2, &1),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(nft.owner_of(&1), user1);
+}
+
+#[test]
+fn test_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    symbol_short!("mint"),
+                    (
+                        &admin,
+                        &user1,
+                        String::from_str(&e, "Central Park NFT"),
+                        String::from_str(&e, "CP"),
+                        String::from_str(&e, "https://example.com/metadata.json"),
+                        40_782_900_i64,
+                        -73_965_400_i64,
+                        100_u32,
+                        false,
+                    )
+                        .into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+
+    nft.transfer(&user1, &user2, &1);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            user1.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    symbol_short!("transfer"),
+                    (&user1, &user2, 1_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+
+    nft.update_location(&admin, &1, &40_781_200, &-73_966_500, &250);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "update_location"),
+                    (&admin, 1_u32, 40_781_200_i64, -73_966_500_i64, 250_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(nft.get_location(&1).radius, 250);
+}
+
+#[test]
+fn test_mint_requires_admin_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let name = String::from_str(&e, "Central Park NFT");
+    let symbol = String::from_str(&e, "CP");
+    let uri = String::from_str(&e, "https://example.com/metadata.json");
+    let latitude = 40_782_900_i64;
+    let longitude = -73_965_400_i64;
+
+    // The recipient cannot mint to themselves, neither in the admin's name
+    // nor in their own.
+    for minter in [&admin, &user1] {
+        let result = nft
+            .mock_auths(&[MockAuth {
+                address: &user1,
+                invoke: &MockAuthInvoke {
+                    contract: &nft.address,
+                    fn_name: "mint",
+                    args: (
+                        minter, &user1, &name, &symbol, &uri, &latitude, &longitude, 100_u32, false,
+                    )
+                        .into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_mint(
+                minter, &user1, &name, &symbol, &uri, &latitude, &longitude, &100, &false,
+            );
+        assert!(result.is_err());
+    }
+    assert_eq!(
+        nft.try_mint(&user1, &user1, &name, &symbol, &uri, &latitude, &longitude, &100, &false),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(nft.total_supply(), 0);
+    assert_eq!(nft.try_owner_of(&1), Err(Ok(Error::TokenNotFound)));
+}
+
+#[test]
+fn test_transfer_requires_owner_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+
+    // Neither the recipient nor the admin can move the owner's token.
+    for signer in [&user2, &admin] {
+        let result = nft
+            .mock_auths(&[MockAuth {
+                address: signer,
+                invoke: &MockAuthInvoke {
+                    contract: &nft.address,
+                    fn_name: "transfer",
+                    args: (&user1, &user2, 1_u32).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_transfer(&user1, &user2, &1);
+        assert!(result.is_err());
+    }
+    assert_eq!(nft.owner_of(&1), user1);
+}
+
+#[test]
+fn test_update_location_requires_admin_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+
+    let latitude = 0_i64;
+    let longitude = 0_i64;
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "update_location",
+                args: (&admin, 1_u32, &latitude, &longitude, 1_u32).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_update_location(&admin, &1, &latitude, &longitude, &1);
+    assert!(result.is_err());
+    assert_eq!(
+        nft.try_update_location(&user1, &1, &latitude, &longitude, &1),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(nft.get_location(&1).radius, 100);
+}
+
+#[test]
+fn test_grant_and_revoke_role() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let manager = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert!(!nft.has_role(&Role::Minter, &manager));
+    nft.grant_role(&Role::Minter, &manager);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "grant_role"),
+                    (Role::Minter, &manager).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "role_granted"), Role::Minter, &manager).into_val(&e),
+                admin.into_val(&e),
+            ),
+        ]
+    );
+    assert!(nft.has_role(&Role::Minter, &manager));
+    assert!(!nft.has_role(&Role::LocationUpdater, &manager));
+
+    // Granting a role twice changes nothing.
+    nft.grant_role(&Role::Minter, &manager);
+    assert_eq!(e.events().all(), vec![&e]);
+
+    // Grants live in persistent storage and are extended whenever used.
+    let role_key = (Role::Minter, manager.clone(), symbol_short!("ROLE"));
+    let role_ttl = || e.as_contract(&nft.address, || e.storage().persistent().get_ttl(&role_key));
+    assert_eq!(role_ttl(), TOKEN_BUMP_AMOUNT);
+    e.ledger().set_sequence_number(20 * DAY_IN_LEDGERS);
+    assert_eq!(role_ttl(), TOKEN_BUMP_AMOUNT - 20 * DAY_IN_LEDGERS);
+    nft.mint_batch(&manager, &vec![&e, mint_request(&e, &manager, 40_782_900)]);
+    assert_eq!(role_ttl(), TOKEN_BUMP_AMOUNT);
+
+    nft.revoke_role(&Role::Minter, &manager);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "role_revoked"), Role::Minter, &manager).into_val(&e),
+                admin.into_val(&e),
+            ),
+        ]
+    );
+    assert!(!nft.has_role(&Role::Minter, &manager));
+    nft.revoke_role(&Role::Minter, &manager);
+    assert_eq!(e.events().all(), vec![&e]);
+
+    // Only the admin can grant roles, even to itself.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &manager,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "grant_role",
+                args: (Role::Minter, &manager).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_grant_role(&Role::Minter, &manager);
+    assert!(result.is_err());
+    assert!(!nft.has_role(&Role::Minter, &manager));
+}
+
+#[test]
+fn test_role_separation() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let ops = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+    let name = String::from_str(&e, "Central Park NFT");
+    let triangle = vec![&e, (0, 0), (1_000_000, 0), (0, 1_000_000)];
+
+    nft.grant_role(&Role::Minter, &minter);
+    nft.grant_role(&Role::LocationUpdater, &ops);
+
+    // The minter can mint, but not adjust locations.
+    let token_id = nft.mint(
+        &minter,
+        &user1,
+        &name,
+        &name,
+        &name,
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    );
+    nft.mint_batch(&minter, &vec![&e, mint_request(&e, &user1, 0)]);
+    assert_eq!(nft.total_supply(), 2);
+    assert_eq!(
+        nft.try_update_location(&minter, &token_id, &0, &0, &10),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(
+        nft.try_set_validity(&minter, &token_id, &None, &Some(0)),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(
+        nft.try_set_polygon(&minter, &token_id, &triangle),
+        Err(Ok(Error::MissingRole))
+    );
+
+    // The ops key can adjust locations, but not mint.
+    nft.update_location(&ops, &token_id, &0, &0, &10);
+    nft.set_validity(&ops, &token_id, &None, &Some(5_000));
+    nft.set_polygon(&ops, &token_id, &triangle);
+    assert_eq!(nft.get_location(&token_id).radius, 10);
+    assert_eq!(
+        nft.try_mint(&ops, &user1, &name, &name, &name, &0, &0, &10, &false),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(
+        nft.try_mint_batch(&ops, &vec![&e, mint_request(&e, &user1, 0)]),
+        Err(Ok(Error::MissingRole))
+    );
+
+    // Neither role reaches admin-only calls.
+    for account in [&minter, &ops] {
+        let result = nft
+            .mock_auths(&[MockAuth {
+                address: account,
+                invoke: &MockAuthInvoke {
+                    contract: &nft.address,
+                    fn_name: "set_max_radius",
+                    args: (1_u32,).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_set_max_radius(&1);
+        assert!(result.is_err());
+    }
+
+    // The history records who made each change.
+    let history = nft.location_history(&token_id, &0, &10);
+    assert_eq!(history.get(0).unwrap().updater, minter);
+    assert_eq!(history.get(1).unwrap().updater, ops);
+
+    // Revoked roles stop working.
+    nft.revoke_role(&Role::Minter, &minter);
+    assert_eq!(
+        nft.try_mint(&minter, &user1, &name, &name, &name, &0, &0, &10, &false),
+        Err(Ok(Error::MissingRole))
+    );
+}
+
+#[test]
+fn test_initialize_twice_rejected() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let attacker = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let result = nft.try_initialize(
+        &attacker,
+        &String::from_str(&e, "Hijacked"),
+        &String::from_str(&e, "HJK"),
+    );
+    assert_eq!(result, Err(Ok(Error::AlreadyInitialized)));
+    assert_eq!(nft.name(), String::from_str(&e, "StellarGeoLinkNFT"));
+    assert_eq!(nft.symbol(), String::from_str(&e, "SGL"));
+}
+
+#[test]
+fn test_not_initialized() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let user1 = Address::generate(&e);
+    let contract_id = e.register(LocationNFT, ());
+    let nft = LocationNFTClient::new(&e, &contract_id);
+
+    assert_eq!(nft.try_name(), Err(Ok(Error::NotInitialized)));
+    assert_eq!(nft.try_symbol(), Err(Ok(Error::NotInitialized)));
+    let result = nft.try_mint(
+        &user1,
+        &user1,
+        &String::from_str(&e, "Central Park NFT"),
+        &String::from_str(&e, "CP"),
+        &String::from_str(&e, "https://example.com/metadata.json"),
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    );
+    assert_eq!(result, Err(Ok(Error::NotInitialized)));
+}
+
+#[test]
+fn test_unknown_token_not_found() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert_eq!(nft.try_get_metadata(&7), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(nft.try_get_location(&7), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(
+        nft.try_transfer(&user1, &user2, &7),
+        Err(Ok(Error::TokenNotFound))
+    );
+    let result = nft.try_update_location(&admin, &7, &40_782_900, &-73_965_400, &100);
+    assert_eq!(result, Err(Ok(Error::TokenNotFound)));
+}
+
+#[test]
+fn test_invalid_coordinates_rejected() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    for (latitude, longitude) in [
+        (90_000_001, 0),
+        (-91_000_000, 0),
+        (0, 180_500_000),
+        (0, -181_000_000),
+        (i64::MAX, i64::MIN),
+    ] {
+        let result = nft.try_mint(
+            &admin,
+            &user1,
+            &String::from_str(&e, "Central Park NFT"),
+            &String::from_str(&e, "CP"),
+            &String::from_str(&e, "https://example.com/metadata.json"),
+            &latitude,
+            &longitude,
+            &100,
+            &false,
+        );
+        assert_eq!(result, Err(Ok(Error::InvalidCoordinate)));
+    }
+    assert_eq!(nft.total_supply(), 0);
+
+    mint(&e, &nft, &user1);
+    let result = nft.try_update_location(&admin, &1, &0, &200_000_000, &100);
+    assert_eq!(result, Err(Ok(Error::InvalidCoordinate)));
+    assert_eq!(nft.get_location(&1).longitude, -73_965_400);
+
+    // The poles and the antimeridian are valid positions.
+    nft.update_location(&admin, &1, &-90_000_000, &180_000_000, &100);
+    nft.update_location(&admin, &1, &90_000_000, &-180_000_000, &100);
+}
+
+#[test]
+fn test_radius_bounded_by_max_radius() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert_eq!(nft.max_radius(), 100_000);
+    mint(&e, &nft, &user1);
+    nft.update_location(&admin, &1, &40_782_900, &-73_965_400, &100_000);
+    let result = nft.try_update_location(&admin, &1, &40_782_900, &-73_965_400, &100_001);
+    assert_eq!(result, Err(Ok(Error::InvalidRadius)));
+
+    nft.set_max_radius(&500);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "set_max_radius"),
+                    (500_u32,).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(nft.max_radius(), 500);
+    let result = nft.try_mint(
+        &admin,
+        &user1,
+        &String::from_str(&e, "Central Park NFT"),
+        &String::from_str(&e, "CP"),
+        &String::from_str(&e, "https://example.com/metadata.json"),
+        &40_782_900,
+        &-73_965_400,
+        &501,
+        &false,
+    );
+    assert_eq!(result, Err(Ok(Error::InvalidRadius)));
+}
+
+fn create_legacy_nft<'a>(e: &Env) -> location_nft_v1::Client<'a> {
+    let contract_id = e.register(location_nft_v1::WASM, ());
+    let legacy = location_nft_v1::Client::new(e, &contract_id);
+    legacy.initialize(
+        &Address::generate(e),
+        &String::from_str(e, "StellarGeoLinkNFT"),
+        &String::from_str(e, "SGL"),
+    );
+    legacy
+}
+
+fn legacy_mint(
+    e: &Env,
+    legacy: &location_nft_v1::Client,
+    to: &Address,
+    token_id: u32,
+    latitude: &str,
+    longitude: &str,
+) {
+    legacy.mint(
+        to,
+        &token_id,
+        &String::from_str(e, "Central Park NFT"),
+        &String::from_str(e, "CP"),
+        &String::from_str(e, "https://example.com/metadata.json"),
+        &String::from_str(e, latitude),
+        &String::from_str(e, longitude),
+        &100,
+    );
+}
+
+#[test]
+fn test_import_legacy() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // Token 7 is minted on the first release, which was then used to move
+    // its location.
+    let legacy = create_legacy_nft(&e);
+    e.ledger().set_timestamp(1_700_000_000);
+    legacy_mint(&e, &legacy, &user1, 7, "40.7829", "-73.9654");
+    legacy.update_location(
+        &7,
+        &String::from_str(&e, "40.783"),
+        &String::from_str(&e, "-73.965"),
+        &120,
+    );
+
+    e.ledger().set_timestamp(1_800_000_000);
+    let token_id = nft.import_legacy(&legacy.address, &user1, &7);
+    assert_eq!(token_id, 1);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "import_legacy"),
+                    (&legacy.address, &user1, 7_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    let imported: Map<Symbol, Val> = map![
+        &e,
+        (Symbol::new(&e, "legacy_id"), 7_u32.into_val(&e)),
+        (Symbol::new(&e, "token_id"), 1_u32.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("mint"), &user1).into_val(&e),
+                location_event_data(&e, Some(1), 40_783_000, -73_965_000, 120),
+            ),
+            (
+                nft.address.clone(),
+                (symbol_short!("imported"), &legacy.address).into_val(&e),
+                imported.into_val(&e),
+            ),
+        ]
+    );
+
+    // The token gets a new id and keeps its owner and metadata, with the
+    // legacy contract's current location.
+    assert_eq!(nft.owner_of(&token_id), user1);
+    assert_eq!(nft.total_supply(), 1);
+    assert_eq!(nft.balance_of(&user1), 1);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, token_id]);
+    assert_eq!(
+        nft.get_location(&token_id),
+        LocationData {
+            latitude: 40_783_000,
+            longitude: -73_965_000,
+            radius: 120,
+            valid_from: None,
+            valid_until: None,
+        }
+    );
+    let metadata = nft.get_metadata(&token_id);
+    assert_eq!(metadata.name, String::from_str(&e, "Central Park NFT"));
+    assert_eq!(metadata.latitude, 40_782_900);
+    assert_eq!(metadata.longitude, -73_965_400);
+    assert_eq!(metadata.created_at, 1_700_000_000);
+    assert_eq!(metadata.updated_at, 1_700_000_000);
+    let cell = (40_783_000, -73_965_000);
+    assert_eq!(nft.tokens_in_cell(&cell, &0, &10), vec![&e, token_id]);
+    assert_eq!(
+        nft.location_history(&token_id, &0, &10),
+        vec![
+            &e,
+            LocationRecord {
+                latitude: 40_783_000,
+                longitude: -73_965_000,
+                radius: 120,
+                timestamp: 1_800_000_000,
+                updater: admin.clone(),
+            },
+        ]
+    );
+
+    // The legacy contract is unchanged.
+    assert!(legacy.is_owner(&user1, &7));
+
+    // Imported tokens behave like minted ones.
+    nft.transfer(&user1, &user2, &token_id);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e, token_id]);
+    assert_eq!(nft.balance_of(&user1), 0);
+    nft.burn(&user2, &token_id);
+    assert_eq!(nft.total_supply(), 0);
+    assert_eq!(nft.balance_of(&user2), 0);
+    assert_eq!(nft.tokens_in_cell(&cell, &0, &10), vec![&e]);
+}
+
+#[test]
+fn test_import_legacy_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let legacy = create_legacy_nft(&e);
+    legacy_mint(&e, &legacy, &user1, 1, "40.7829", "-73.9654");
+    // The first release did not range check coordinates.
+    legacy_mint(&e, &legacy, &user1, 2, "200.5", "-73.9654");
+    legacy_mint(&e, &legacy, &user1, 3, "40.7829", "-73.9654");
+
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user2, &1),
+        Err(Ok(Error::NotOwner))
+    );
+    // The first release cannot tell an unknown id from one owned by
+    // someone else.
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user1, &9),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(
+        nft.try_import_legacy(&Address::generate(&e), &user1, &1),
+        Err(Ok(Error::TokenNotFound))
+    );
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user1, &2),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+    nft.set_max_radius(&50);
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user1, &1),
+        Err(Ok(Error::InvalidRadius))
+    );
+    nft.set_max_radius(&100);
+
+    // A token is imported once, even after it is burned here.
+    let token_id = nft.import_legacy(&legacy.address, &user1, &1);
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user1, &1),
+        Err(Ok(Error::TokenExists))
+    );
+    nft.burn(&user1, &token_id);
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user1, &1),
+        Err(Ok(Error::TokenExists))
+    );
+    // The same id in another first-release contract is another token.
+    let other = create_legacy_nft(&e);
+    legacy_mint(&e, &other, &user1, 1, "40.7829", "-73.9654");
+    assert_eq!(nft.import_legacy(&other.address, &user1, &1), 2);
+    assert_eq!(nft.total_supply(), 1);
+
+    // Only the admin can import.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "import_legacy",
+                args: (&legacy.address, &user1, 4_u32).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_import_legacy(&legacy.address, &user1, &4);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_parse_microdegrees() {
+    let e = Env::default();
+    let parse = |s: &str| parse_microdegrees(&String::from_str(&e, s));
+
+    assert_eq!(parse("40.7829"), Some(40_782_900));
+    assert_eq!(parse("-73.9654"), Some(-73_965_400));
+    assert_eq!(parse("+180"), Some(180_000_000));
+    assert_eq!(parse("0.1234567"), Some(123_456));
+    assert_eq!(parse(".5"), Some(500_000));
+    assert_eq!(parse("-"), None);
+    assert_eq!(parse("1e5"), None);
+    assert_eq!(parse(" 1"), None);
+}
+
+#[test]
+fn test_admin_rotation() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin1 = Address::generate(&e);
+    let admin2 = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin1);
+
+    nft.set_admin(&admin2);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin1.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    symbol_short!("set_admin"),
+                    (&admin2,).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "admin_proposed"), &admin1).into_val(&e),
+                admin2.into_val(&e),
+            ),
+        ]
+    );
+    // The proposal alone does not hand over control.
+    assert_eq!(nft.admin(), admin1);
+    assert_eq!(nft.pending_admin(), Some(admin2.clone()));
+
+    nft.accept_admin();
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin2.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "accept_admin"),
+                    ().into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("set_admin"), &admin1).into_val(&e),
+                admin2.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.admin(), admin2);
+    assert_eq!(nft.pending_admin(), None);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(e.auths()[0].0, admin2);
+}
+
+#[test]
+fn test_accept_admin_without_proposal() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert_eq!(nft.try_accept_admin(), Err(Ok(Error::NoPendingAdmin)));
+}
+
+#[test]
+fn test_admin_rotation_requires_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let attacker = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // Only the admin can propose a successor.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_admin",
+                args: (&attacker,).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_admin(&attacker);
+    assert!(result.is_err());
+    assert_eq!(nft.pending_admin(), None);
+
+    // Only the proposed admin can accept.
+    let admin2 = Address::generate(&e);
+    nft.set_admin(&admin2);
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "accept_admin",
+                args: ().into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_accept_admin();
+    assert!(result.is_err());
+    assert_eq!(nft.admin(), admin);
+}
+
+fn mint_at(e: &Env, nft: &LocationNFTClient, latitude: i64, longitude: i64, radius: u32) -> u32 {
+    nft.mint(
+        &nft.admin(),
+        &Address::generate(e),
+        &String::from_str(e, "Proximity NFT"),
+        &String::from_str(e, "PRX"),
+        &String::from_str(e, "https://example.com/metadata.json"),
+        &latitude,
+        &longitude,
+        &radius,
+        &false,
+    )
+}
+
+#[test]
+fn test_is_within_radius_at_equator() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // One meter is about 8.99 microdegrees along the equator.
+    mint_at(&e, &nft, 0, 0, 1000);
+    assert!(nft.is_within_radius(&1, &0, &0));
+    assert!(nft.is_within_radius(&1, &0, &8_990));
+    assert!(!nft.is_within_radius(&1, &0, &9_000));
+    assert!(nft.is_within_radius(&1, &-8_990, &0));
+    assert!(!nft.is_within_radius(&1, &-9_000, &0));
+    // 707 m north and 707 m east.
+    assert!(nft.is_within_radius(&1, &6_358, &6_358));
+    assert!(!nft.is_within_radius(&1, &6_400, &6_400));
+}
+
+#[test]
+fn test_is_within_radius_at_high_latitude() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // At 60 degrees a degree of longitude is half as long as at the equator.
+    mint_at(&e, &nft, 60_000_000, 10_000_000, 1000);
+    assert!(nft.is_within_radius(&1, &60_000_000, &10_017_960));
+    assert!(!nft.is_within_radius(&1, &60_000_000, &10_018_040));
+    assert!(nft.is_within_radius(&1, &60_008_990, &10_000_000));
+    assert!(!nft.is_within_radius(&1, &60_009_000, &10_000_000));
+
+    // Near the pole a large longitude difference is only a short distance.
+    mint_at(&e, &nft, 89_990_000, 0, 200);
+    assert!(nft.is_within_radius(&2, &89_990_000, &10_000_000));
+    assert!(!nft.is_within_radius(&2, &89_990_000, &12_000_000));
+}
+
+#[test]
+fn test_is_within_radius_across_antimeridian() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // 2,000 microdegrees apart the short way round: about 222 m.
+    mint_at(&e, &nft, 0, 179_999_000, 300);
+    assert!(nft.is_within_radius(&1, &0, &-179_999_000));
+    assert!(nft.is_within_radius(&1, &0, &180_000_000));
+    mint_at(&e, &nft, 0, -179_999_000, 200);
+    assert!(!nft.is_within_radius(&2, &0, &179_999_000));
+    assert!(nft.is_within_radius(&2, &0, &-179_999_500));
+}
+
+#[test]
+fn test_is_within_radius_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint_at(&e, &nft, 0, 0, 1000);
+    assert_eq!(
+        nft.try_is_within_radius(&1, &91_000_000, &0),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+    assert_eq!(
+        nft.try_is_within_radius(&2, &0, &0),
+        Err(Ok(Error::TokenNotFound))
+    );
+}
+
+#[test]
+fn test_validity_window() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(1_000);
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint_at(&e, &nft, 0, 0, 1000);
+    assert!(nft.is_active(&1));
+    assert!(nft.is_within_radius(&1, &0, &0));
+
+    nft.set_validity(&admin, &1, &Some(2_000), &Some(3_000));
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (Symbol::new(&e, "valid_from"), Some(2_000_u64).into_val(&e)),
+        (Symbol::new(&e, "valid_until"), Some(3_000_u64).into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "validity_updated"), 1_u32).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+
+    // Before the window.
+    assert!(!nft.is_active(&1));
+    assert_eq!(
+        nft.try_is_within_radius(&1, &0, &0),
+        Err(Ok(Error::TokenNotActive))
+    );
+
+    // Both ends of the window are inclusive.
+    e.ledger().set_timestamp(2_000);
+    assert!(nft.is_active(&1));
+    assert!(nft.is_within_radius(&1, &0, &0));
+    e.ledger().set_timestamp(3_000);
+    assert!(nft.is_active(&1));
+
+    // Moving the token keeps its window.
+    nft.update_location(&admin, &1, &0, &0, &500);
+    assert_eq!(nft.get_location(&1).valid_until, Some(3_000));
+
+    // After the window.
+    e.ledger().set_timestamp(3_001);
+    assert!(!nft.is_active(&1));
+    assert_eq!(
+        nft.try_is_within_radius(&1, &0, &0),
+        Err(Ok(Error::TokenNotActive))
+    );
+
+    // Open-ended windows.
+    nft.set_validity(&admin, &1, &Some(2_000), &None);
+    assert!(nft.is_active(&1));
+    nft.set_validity(&admin, &1, &None, &Some(3_000));
+    assert!(!nft.is_active(&1));
+    nft.set_validity(&admin, &1, &None, &None);
+    assert!(nft.is_active(&1));
+}
+
+#[test]
+fn test_validity_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(nft.try_is_active(&2), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(
+        nft.try_set_validity(&admin, &2, &None, &None),
+        Err(Ok(Error::TokenNotFound))
+    );
+    assert_eq!(
+        nft.try_set_validity(&admin, &1, &Some(3_000), &Some(2_000)),
+        Err(Ok(Error::InvalidTimeWindow))
+    );
+
+    // Only the admin or a location updater can set a token's window.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_validity",
+                args: (&admin, 1_u32, Some(0_u64), Some(0_u64)).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_validity(&admin, &1, &Some(0), &Some(0));
+    assert!(result.is_err());
+    assert_eq!(
+        nft.try_set_validity(&user1, &1, &Some(0), &Some(0)),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(nft.get_location(&1).valid_from, None);
+}
+
+#[test]
+fn test_is_within_polygon_concave() {
+    let e = Env::default();
+
+    // A U shape opening north: two 1-degree-wide arms joined along the south.
+    let u_shape = vec![
+        &e,
+        (0, 0),
+        (0, 3_000_000),
+        (3_000_000, 3_000_000),
+        (3_000_000, 2_000_000),
+        (1_000_000, 2_000_000),
+        (1_000_000, 1_000_000),
+        (3_000_000, 1_000_000),
+        (3_000_000, 0),
+    ];
+    // Inside each arm and the base.
+    assert!(is_within_polygon(&u_shape, 2_000_000, 500_000));
+    assert!(is_within_polygon(&u_shape, 2_000_000, 2_500_000));
+    assert!(is_within_polygon(&u_shape, 500_000, 1_500_000));
+    // In the notch between the arms, level with both of them.
+    assert!(!is_within_polygon(&u_shape, 2_000_000, 1_500_000));
+    assert!(!is_within_polygon(&u_shape, 2_999_999, 1_500_000));
+    // Level with the reflex vertices, inside and out.
+    assert!(is_within_polygon(&u_shape, 1_000_000, 500_000));
+    assert!(!is_within_polygon(&u_shape, 1_000_000, 3_500_000));
+    // Outside the bounding box.
+    assert!(!is_within_polygon(&u_shape, -1, 1_500_000));
+    assert!(!is_within_polygon(&u_shape, 1_500_000, -1_000_000));
+    // Edges and vertices count as inside.
+    assert!(is_within_polygon(&u_shape, 0, 1_500_000));
+    assert!(is_within_polygon(&u_shape, 2_000_000, 1_000_000));
+    assert!(is_within_polygon(&u_shape, 1_000_000, 1_000_000));
+    assert!(is_within_polygon(&u_shape, 3_000_000, 3_000_000));
+
+    // A star whose points stick out past its concave waist.
+    let star = vec![
+        &e,
+        (0, 2_000_000),
+        (1_400_000, 2_600_000),
+        (3_000_000, 2_000_000),
+        (2_400_000, 3_400_000),
+        (3_000_000, 5_000_000),
+        (1_400_000, 4_200_000),
+        (0, 5_000_000),
+        (800_000, 3_400_000),
+    ];
+    assert!(is_within_polygon(&star, 1_500_000, 3_400_000));
+    assert!(is_within_polygon(&star, 200_000, 2_200_000));
+    assert!(!is_within_polygon(&star, 1_500_000, 2_100_000));
+    assert!(!is_within_polygon(&star, 400_000, 3_400_000));
+
+    // A square straddling the antimeridian.
+    let pacific = vec![
+        &e,
+        (-1_000_000, 179_000_000),
+        (-1_000_000, -179_000_000),
+        (1_000_000, -179_000_000),
+        (1_000_000, 179_000_000),
+    ];
+    assert!(is_within_polygon(&pacific, 0, 180_000_000));
+    assert!(is_within_polygon(&pacific, 0, -179_500_000));
+    assert!(!is_within_polygon(&pacific, 0, 0));
+    assert!(!is_within_polygon(&pacific, 0, 178_000_000));
+
+    assert!(!is_within_polygon(&vec![&e], 0, 0));
+}
+
+#[test]
+fn test_polygon_geofence() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(1_000);
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(nft.get_polygon(&1), vec![&e]);
+    assert_eq!(
+        nft.try_is_within_polygon(&1, &40_782_900, &-73_965_400),
+        Err(Ok(Error::PolygonNotSet))
+    );
+
+    // An L-shaped park around the token's center.
+    let park = vec![
+        &e,
+        (40_780_000, -73_970_000),
+        (40_790_000, -73_970_000),
+        (40_790_000, -73_965_000),
+        (40_785_000, -73_965_000),
+        (40_785_000, -73_960_000),
+        (40_780_000, -73_960_000),
+    ];
+    nft.set_polygon(&admin, &1, &park);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "polygon_updated"), 1_u32).into_val(&e),
+                park.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.get_polygon(&1), park);
+    assert!(nft.is_within_polygon(&1, &40_788_000, &-73_968_000));
+    assert!(nft.is_within_polygon(&1, &40_782_000, &-73_962_000));
+    // The cut-out corner of the L.
+    assert!(!nft.is_within_polygon(&1, &40_788_000, &-73_962_000));
+    // The radius check is unaffected.
+    assert!(nft.is_within_radius(&1, &40_782_900, &-73_965_400));
+
+    // The polygon is subject to the token's validity window.
+    nft.set_validity(&admin, &1, &Some(2_000), &None);
+    assert_eq!(
+        nft.try_is_within_polygon(&1, &40_788_000, &-73_968_000),
+        Err(Ok(Error::TokenNotActive))
+    );
+    nft.set_validity(&admin, &1, &None, &None);
+
+    // An empty list removes the polygon.
+    nft.set_polygon(&admin, &1, &vec![&e]);
+    assert_eq!(nft.get_polygon(&1), vec![&e]);
+    assert_eq!(
+        nft.try_is_within_polygon(&1, &40_788_000, &-73_968_000),
+        Err(Ok(Error::PolygonNotSet))
+    );
+
+    // Burning the token removes its polygon.
+    nft.set_polygon(&admin, &1, &park);
+    nft.burn(&user1, &1);
+    e.as_contract(&nft.address, || {
+        assert!(!e
+            .storage()
+            .persistent()
+            .has(&(1_u32, symbol_short!("POLYGON"))));
+    });
+}
+
+#[test]
+fn test_polygon_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    let triangle = vec![&e, (0, 0), (1_000_000, 0), (0, 1_000_000)];
+    assert_eq!(
+        nft.try_set_polygon(&admin, &2, &triangle),
+        Err(Ok(Error::TokenNotFound))
+    );
+    assert_eq!(nft.try_get_polygon(&2), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(
+        nft.try_is_within_polygon(&2, &0, &0),
+        Err(Ok(Error::TokenNotFound))
+    );
+    assert_eq!(
+        nft.try_is_within_polygon(&1, &91_000_000, &0),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+
+    // Too few vertices, too many, or a vertex off the globe.
+    assert_eq!(
+        nft.try_set_polygon(&admin, &1, &vec![&e, (0, 0), (1_000_000, 0)]),
+        Err(Ok(Error::InvalidPolygon))
+    );
+    let mut circle = vec![&e];
+    for i in 0..=MAX_POLYGON_VERTICES as i64 {
+        circle.push_back((i * 1_000, i * i * 1_000));
+    }
+    assert_eq!(
+        nft.try_set_polygon(&admin, &1, &circle),
+        Err(Ok(Error::InvalidPolygon))
+    );
+    circle.pop_back();
+    nft.set_polygon(&admin, &1, &circle);
+    assert_eq!(
+        nft.try_set_polygon(
+            &admin,
+            &1,
+            &vec![&e, (0, 0), (1_000_000, 0), (0, 181_000_000)]
+        ),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+    assert_eq!(nft.get_polygon(&1), circle);
+
+    // Only the admin or a location updater can set a polygon.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_polygon",
+                args: (&admin, 1_u32, &triangle).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_polygon(&admin, &1, &triangle);
+    assert!(result.is_err());
+    assert_eq!(
+        nft.try_set_polygon(&user1, &1, &triangle),
+        Err(Ok(Error::MissingRole))
+    );
+    assert_eq!(nft.get_polygon(&1), circle);
+}
+
+#[test]
+fn test_distance_meters() {
+    assert_eq!(distance_meters(0, 0, 0, 0), 0);
+    assert_eq!(distance_meters(0, 0, 1_000_000, 0), 111_195);
+    assert_eq!(distance_meters(0, 0, 0, 1_000_000), 111_195);
+    assert_eq!(distance_meters(0, 0, 0, -1_000_000), 111_195);
+    assert_eq!(
+        distance_meters(60_000_000, 0, 60_000_000, 1_000_000),
+        55_597
+    );
+    assert_eq!(distance_meters(0, 179_500_000, 0, -179_500_000), 111_195);
+    assert_eq!(distance_meters(-90_000_000, 0, 90_000_000, 0), 20_015_114);
+}
+
+struct DropSetup<'a> {
+    nft: LocationNFTClient<'a>,
+    oracle: SigningKey,
+    drop_id: u32,
+}
+
+fn create_drop_setup<'a>(e: &Env) -> DropSetup<'a> {
+    e.mock_all_auths();
+    e.ledger().set_timestamp(2_000);
+
+    let admin = Address::generate(e);
+    let nft = create_nft(e, &admin);
+    let oracle = SigningKey::from_bytes(&[7; 32]);
+    nft.set_oracle(&BytesN::from_array(e, &oracle.verifying_key().to_bytes()));
+
+    // Two tokens claimable within 100 m of Central Park between t=1000 and t=5000.
+    let drop_id = nft.create_drop(
+        &String::from_str(e, "Central Park Drop"),
+        &String::from_str(e, "CPD"),
+        &String::from_str(e, "https://example.com/drop.json"),
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &2,
+        &1_000,
+        &5_000,
+    );
+    DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    }
+}
+
+fn attest(
+    e: &Env,
+    contract: &Address,
+    oracle: &SigningKey,
+    claimer: &Address,
+    drop_id: u32,
+    latitude: i64,
+    longitude: i64,
+    timestamp: u64,
+) -> (PositionAttestation, BytesN<64>) {
+    let attestation = PositionAttestation {
+        contract: contract.clone(),
+        claimer: claimer.clone(),
+        drop_id,
+        latitude,
+        longitude,
+        timestamp,
+    };
+    let message = attestation.clone().to_xdr(e);
+    let mut buf = std::vec![0u8; message.len() as usize];
+    message.copy_into_slice(&mut buf);
+    let signature = oracle.sign(&buf).to_bytes();
+    (attestation, BytesN::from_array(e, &signature))
+}
+
+#[test]
+fn test_claim_drop() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let user1 = Address::generate(&e);
+
+    // About 50 m north of the drop center.
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_783_350,
+        -73_965_400,
+        1_990,
+    );
+    let token_id = nft.claim(&user1, &drop_id, &attestation, &signature);
+    assert_eq!(token_id, 1);
+    assert_eq!(e.auths()[0].0, user1);
+
+    assert_eq!(nft.owner_of(&1), user1);
+    assert_eq!(nft.total_supply(), 1);
+    assert_eq!(
+        nft.get_location(&1),
+        LocationData {
+            latitude: 40_782_900,
+            longitude: -73_965_400,
+            radius: 100,
+            valid_from: None,
+            valid_until: None,
+        }
+    );
+    assert_eq!(
+        nft.get_metadata(&1).name,
+        String::from_str(&e, "Central Park Drop")
+    );
+    assert_eq!(nft.get_drop(&drop_id).claimed, 1);
+
+    // The same claimer cannot claim twice, even with a fresh attestation.
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::AlreadyClaimed))
+    );
+}
+
+#[test]
+fn test_claim_drop_out_of_range() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let user1 = Address::generate(&e);
+
+    // About 150 m north of the drop center.
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_784_250,
+        -73_965_400,
+        2_000,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::OutOfRange))
+    );
+    assert_eq!(nft.total_supply(), 0);
+}
+
+#[test]
+fn test_claim_drop_rejects_bad_attestations() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+
+    // Issued for someone else.
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user2,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::InvalidAttestation))
+    );
+
+    // Issued for a drop with the same id on another collection that shares
+    // the oracle.
+    let other_collection = Address::generate(&e);
+    let (attestation, signature) = attest(
+        &e,
+        &other_collection,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::InvalidAttestation))
+    );
+
+    // Too far in the future.
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_061,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::InvalidAttestation))
+    );
+
+    // Too old.
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        1_699,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::AttestationExpired))
+    );
+
+    // Signed by a key other than the oracle.
+    let impostor = SigningKey::from_bytes(&[8; 32]);
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &impostor,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    assert!(nft
+        .try_claim(&user1, &drop_id, &attestation, &signature)
+        .is_err());
+
+    // Signed by the oracle but altered afterwards.
+    let (mut attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_900_000,
+        -73_965_400,
+        2_000,
+    );
+    attestation.latitude = 40_782_900;
+    assert!(nft
+        .try_claim(&user1, &drop_id, &attestation, &signature)
+        .is_err());
+
+    assert_eq!(nft.total_supply(), 0);
+}
+
+#[test]
+fn test_claim_drop_window_and_supply() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let users = [
+        Address::generate(&e),
+        Address::generate(&e),
+        Address::generate(&e),
+    ];
+
+    e.ledger().set_timestamp(999);
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &users[0],
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        999,
+    );
+    assert_eq!(
+        nft.try_claim(&users[0], &drop_id, &attestation, &signature),
+        Err(Ok(Error::DropNotActive))
+    );
+
+    e.ledger().set_timestamp(3_000);
+    for user in users.iter().take(2) {
+        let (attestation, signature) = attest(
+            &e,
+            &nft.address,
+            &oracle,
+            user,
+            drop_id,
+            40_782_900,
+            -73_965_400,
+            3_000,
+        );
+        nft.claim(user, &drop_id, &attestation, &signature);
+    }
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &users[2],
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        3_000,
+    );
+    assert_eq!(
+        nft.try_claim(&users[2], &drop_id, &attestation, &signature),
+        Err(Ok(Error::DropExhausted))
+    );
+
+    e.ledger().set_timestamp(5_001);
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &users[2],
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        5_001,
+    );
+    assert_eq!(
+        nft.try_claim(&users[2], &drop_id, &attestation, &signature),
+        Err(Ok(Error::DropNotActive))
+    );
+    assert_eq!(nft.total_supply(), 2);
+}
+
+#[test]
+fn test_drop_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+    let oracle = SigningKey::from_bytes(&[7; 32]);
+
+    let create = |start_time: u64, end_time: u64| {
+        nft.try_create_drop(
+            &String::from_str(&e, "Central Park Drop"),
+            &String::from_str(&e, "CPD"),
+            &String::from_str(&e, "https://example.com/drop.json"),
+            &40_782_900,
+            &-73_965_400,
+            &100,
+            &1,
+            &start_time,
+            &end_time,
+        )
+    };
+    assert_eq!(create(5_000, 1_000), Err(Ok(Error::InvalidTimeWindow)));
+    assert_eq!(create(0, 1_000), Ok(Ok(1)));
+    assert_eq!(nft.try_get_drop(&2), Err(Ok(Error::DropNotFound)));
+    assert_eq!(nft.try_oracle(), Err(Ok(Error::OracleNotSet)));
+
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        1,
+        40_782_900,
+        -73_965_400,
+        0,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &1, &attestation, &signature),
+        Err(Ok(Error::OracleNotSet))
+    );
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        2,
+        40_782_900,
+        -73_965_400,
+        0,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &2, &attestation, &signature),
+        Err(Ok(Error::DropNotFound))
+    );
+}
+
+#[test]
+fn test_drop_admin_functions_require_admin_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let attacker = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+    let key = BytesN::from_array(&e, &[1; 32]);
+
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_oracle",
+                args: (&key,).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_oracle(&key);
+    assert!(result.is_err());
+
+    nft.create_drop(
+        &String::from_str(&e, "Central Park Drop"),
+        &String::from_str(&e, "CPD"),
+        &String::from_str(&e, "https://example.com/drop.json"),
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &1,
+        &0,
+        &1_000,
+    );
+    assert_eq!(e.auths()[0].0, admin);
+}
+
+fn location_event_data(
+    e: &Env,
+    token_id: Option<u32>,
+    latitude: i64,
+    longitude: i64,
+    radius: u32,
+) -> Val {
+    let mut data: Map<Symbol, Val> = map![
+        e,
+        (symbol_short!("latitude"), latitude.into_val(e)),
+        (Symbol::new(e, "longitude"), longitude.into_val(e)),
+        (symbol_short!("radius"), radius.into_val(e)),
+    ];
+    if let Some(token_id) = token_id {
+        data.set(symbol_short!("token_id"), token_id.into_val(e));
+    }
+    data.into_val(e)
+}
+
+#[test]
+fn test_events() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("mint"), &user1).into_val(&e),
+                location_event_data(&e, Some(1), 40_782_900, -73_965_400, 100),
+            ),
+        ]
+    );
+
+    nft.transfer(&user1, &user2, &1);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("transfer"), &user1, &user2).into_val(&e),
+                1_u32.into_val(&e),
+            ),
+        ]
+    );
+
+    nft.update_location(&admin, &1, &40_781_200, &-73_966_500, &250);
+    let mut data: Map<Symbol, Val> =
+        location_event_data(&e, None, 40_781_200, -73_966_500, 250).into_val(&e);
+    data.set(symbol_short!("updater"), admin.into_val(&e));
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "location_updated"), 1_u32).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_failed_calls_emit_no_events() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert!(nft.try_transfer(&user2, &user1, &1).is_err());
+    assert_eq!(e.events().all(), vec![&e]);
+}
+
+#[test]
+fn test_claim_emits_mint_event() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let user1 = Address::generate(&e);
+
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    nft.claim(&user1, &drop_id, &attestation, &signature);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("mint"), &user1).into_val(&e),
+                location_event_data(&e, Some(1), 40_782_900, -73_965_400, 100),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_tokens_of_owner() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    for _ in 0..4 {
+        mint(&e, &nft, &user1);
+    }
+    assert_eq!(nft.balance_of(&user1), 4);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 1, 2, 3, 4]);
+    assert_eq!(nft.tokens_of_owner(&user1, &1, &2), vec![&e, 2, 3]);
+    assert_eq!(nft.tokens_of_owner(&user1, &3, &2), vec![&e, 4]);
+    assert_eq!(nft.tokens_of_owner(&user1, &4, &2), vec![&e]);
+    assert_eq!(nft.tokens_of_owner(&user1, &u32::MAX, &u32::MAX), vec![&e]);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e]);
+
+    // Moving a token out fills its slot with the owner's last token.
+    nft.transfer(&user1, &user2, &2);
+    assert_eq!(nft.balance_of(&user1), 3);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 1, 4, 3]);
+    assert_eq!(nft.balance_of(&user2), 1);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e, 2]);
+
+    nft.transfer(&user1, &user2, &3);
+    nft.transfer(&user2, &user1, &2);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 1, 4, 2]);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e, 3]);
+
+    // Transferring to yourself keeps the balance unchanged.
+    nft.transfer(&user2, &user2, &3);
+    assert_eq!(nft.balance_of(&user2), 1);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e, 3]);
+}
+
+#[test]
+fn test_tokens_of_owner_page_size_capped() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    for _ in 0..55 {
+        mint(&e, &nft, &user1);
+    }
+    assert_eq!(nft.balance_of(&user1), 55);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &1_000).len(), 50);
+    let rest = nft.tokens_of_owner(&user1, &50, &1_000);
+    assert_eq!(rest, vec![&e, 51, 52, 53, 54, 55]);
+}
+
+#[test]
+fn test_burn() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    mint(&e, &nft, &user1);
+    assert_eq!(nft.total_supply(), 2);
+
+    nft.burn(&user1, &1);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            user1.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    symbol_short!("burn"),
+                    (&user1, 1_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("burn"), &user1).into_val(&e),
+                1_u32.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.total_supply(), 1);
+    assert_eq!(nft.balance_of(&user1), 1);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 2]);
+    assert!(!nft.is_owner(&user1, &1));
+    assert_eq!(nft.try_owner_of(&1), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(nft.try_get_metadata(&1), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(nft.try_get_location(&1), Err(Ok(Error::TokenNotFound)));
+
+    // Burning twice fails.
+    assert_eq!(nft.try_burn(&user1, &1), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(nft.total_supply(), 1);
+
+    // The admin can burn any token.
+    nft.burn(&admin, &2);
+    assert_eq!(e.auths()[0].0, admin);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("burn"), &user1).into_val(&e),
+                2_u32.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.total_supply(), 0);
+    assert_eq!(nft.balance_of(&user1), 0);
+}
+
+#[test]
+fn test_burn_after_transfer() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    nft.transfer(&user1, &user2, &1);
+
+    // The previous owner can no longer burn it.
+    assert_eq!(nft.try_burn(&user1, &1), Err(Ok(Error::NotOwner)));
+
+    nft.burn(&user2, &1);
+    assert_eq!(nft.balance_of(&user1), 0);
+    assert_eq!(nft.balance_of(&user2), 0);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e]);
+    assert_eq!(nft.total_supply(), 0);
+    assert_eq!(
+        nft.try_transfer(&user2, &user1, &1),
+        Err(Ok(Error::TokenNotFound))
+    );
+}
+
+#[test]
+fn test_burn_requires_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &admin,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "burn",
+                args: (&user1, 1_u32).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_burn(&user1, &1);
+    assert!(result.is_err());
+    assert_eq!(nft.owner_of(&1), user1);
+}
+
+#[test]
+fn test_approve_and_transfer_from() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(nft.get_approved(&1), None);
+    assert_eq!(
+        nft.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(Error::NotApproved))
+    );
+
+    nft.approve(&user1, &spender, &1, &200);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            user1.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    symbol_short!("approve"),
+                    (&user1, &spender, 1_u32, 200_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (symbol_short!("approved"), spender.into_val(&e)),
+        (Symbol::new(&e, "expiration_ledger"), 200_u32.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("approve"), &user1, 1_u32).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.get_approved(&1), Some(spender.clone()));
+
+    nft.transfer_from(&spender, &user1, &user2, &1);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            spender.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "transfer_from"),
+                    (&spender, &user1, &user2, 1_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("transfer"), &user1, &user2).into_val(&e),
+                1_u32.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.owner_of(&1), user2);
+    assert_eq!(nft.balance_of(&user1), 0);
+    assert_eq!(nft.balance_of(&user2), 1);
+
+    // The approval does not survive the transfer.
+    assert_eq!(nft.get_approved(&1), None);
+    assert_eq!(
+        nft.try_transfer_from(&spender, &user2, &user1, &1),
+        Err(Ok(Error::NotApproved))
+    );
+}
+
+#[test]
+fn test_approval_expiration() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_sequence_number(100);
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(
+        nft.try_approve(&user1, &spender, &1, &99),
+        Err(Ok(Error::InvalidExpiration))
+    );
+
+    nft.approve(&user1, &spender, &1, &150);
+    e.ledger().set_sequence_number(151);
+    assert_eq!(nft.get_approved(&1), None);
+    assert_eq!(
+        nft.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(Error::NotApproved))
+    );
+
+    // An expiration of 0 revokes a live approval.
+    nft.approve(&user1, &spender, &1, &200);
+    nft.approve(&user1, &spender, &1, &0);
+    assert_eq!(nft.get_approved(&1), None);
+    assert_eq!(
+        nft.try_transfer_from(&spender, &user1, &user2, &1),
+        Err(Ok(Error::NotApproved))
+    );
+    assert_eq!(nft.owner_of(&1), user1);
+}
+
+#[test]
+fn test_operator_approval() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_sequence_number(100);
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let operator = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    mint(&e, &nft, &user1);
+    mint(&e, &nft, &user1);
+    assert!(!nft.is_approved_for_all(&user1, &operator));
+
+    nft.set_approval_for_all(&user1, &operator, &200);
+    assert_eq!(e.auths()[0].0, user1);
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (symbol_short!("operator"), operator.into_val(&e)),
+        (Symbol::new(&e, "expiration_ledger"), 200_u32.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "approve_for_all"), &user1).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert!(nft.is_approved_for_all(&user1, &operator));
+
+    // An operator can move any of the owner's tokens and approve others.
+    nft.transfer_from(&operator, &user1, &user2, &1);
+    assert_eq!(nft.owner_of(&1), user2);
+    nft.approve(&operator, &spender, &2, &200);
+    assert_eq!(nft.get_approved(&2), Some(spender.clone()));
+    nft.transfer_from(&spender, &user1, &user2, &2);
+    assert_eq!(nft.owner_of(&2), user2);
+
+    // Operator rights do not extend to the new owner's tokens.
+    assert_eq!(
+        nft.try_transfer_from(&operator, &user2, &user1, &1),
+        Err(Ok(Error::NotApproved))
+    );
+
+    e.ledger().set_sequence_number(201);
+    assert!(!nft.is_approved_for_all(&user1, &operator));
+    assert_eq!(
+        nft.try_transfer_from(&operator, &user1, &user2, &3),
+        Err(Ok(Error::NotApproved))
+    );
+
+    nft.set_approval_for_all(&user1, &operator, &300);
+    nft.set_approval_for_all(&user1, &operator, &0);
+    assert!(!nft.is_approved_for_all(&user1, &operator));
+    assert_eq!(
+        nft.try_set_approval_for_all(&user1, &operator, &200),
+        Err(Ok(Error::InvalidExpiration))
+    );
+}
+
+#[test]
+fn test_approval_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let spender = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert_eq!(
+        nft.try_approve(&user1, &spender, &1, &200),
+        Err(Ok(Error::TokenNotFound))
+    );
+    mint(&e, &nft, &user1);
+
+    // Only the owner or an operator can approve.
+    assert_eq!(
+        nft.try_approve(&user2, &spender, &1, &200),
+        Err(Ok(Error::NotApproved))
+    );
+    assert_eq!(
+        nft.try_transfer_from(&spender, &user2, &spender, &1),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(
+        nft.try_transfer_from(&spender, &user1, &spender, &2),
+        Err(Ok(Error::TokenNotFound))
+    );
+
+    // The approved spender still has to authorize the transfer.
+    nft.approve(&user1, &spender, &1, &200);
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user2,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "transfer_from",
+                args: (&spender, &user1, &user2, 1_u32).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_transfer_from(&spender, &user1, &user2, &1);
+    assert!(result.is_err());
+    assert_eq!(nft.owner_of(&1), user1);
+}
+
+#[test]
+fn test_soulbound_token() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    let token_id = nft.mint(
+        &admin,
+        &user1,
+        &String::from_str(&e, "Visit Badge"),
+        &String::from_str(&e, "VB"),
+        &String::from_str(&e, "https://example.com/badge.json"),
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &true,
+    );
+    assert!(!nft.is_soulbound(&1));
+    assert!(nft.is_soulbound(&token_id));
+
+    // Neither the owner nor an operator can move the badge.
+    nft.set_approval_for_all(&user1, &user2, &1_000);
+    assert_eq!(
+        nft.try_transfer(&user1, &user2, &token_id),
+        Err(Ok(Error::Soulbound))
+    );
+    assert_eq!(
+        nft.try_transfer_from(&user2, &user1, &user2, &token_id),
+        Err(Ok(Error::Soulbound))
+    );
+    assert_eq!(
+        nft.try_transfer_batch(&user1, &user2, &vec![&e, 1, token_id]),
+        Err(Ok(Error::Soulbound))
+    );
+    assert_eq!(nft.owner_of(&1), user1);
+    assert_eq!(nft.owner_of(&token_id), user1);
+
+    // Other tokens stay transferable.
+    nft.transfer(&user1, &user2, &1);
+    assert_eq!(nft.owner_of(&1), user2);
+
+    // The admin can still revoke the badge.
+    nft.burn(&admin, &token_id);
+    assert_eq!(nft.balance_of(&user1), 0);
+    assert!(!nft.is_soulbound(&token_id));
+}
+
+#[test]
+fn test_default_soulbound() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+
+    assert!(!nft.default_soulbound());
+    mint(&e, &nft, &user1);
+    nft.set_default_soulbound(&true);
+    assert!(nft.default_soulbound());
+
+    // Tokens minted while the default is set are soulbound, including
+    // claims and batch mints that do not ask for it.
+    mint(&e, &nft, &user1);
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    let claimed = nft.claim(&user1, &drop_id, &attestation, &signature);
+    nft.mint_batch(
+        &nft.admin(),
+        &vec![&e, mint_request(&e, &user1, 40_000_000)],
+    );
+    for token_id in [2, claimed, 4] {
+        assert!(nft.is_soulbound(&token_id));
+        assert_eq!(
+            nft.try_transfer(&user1, &user2, &token_id),
+            Err(Ok(Error::Soulbound))
+        );
+    }
+
+    // Tokens minted before keep their flag, in both directions.
+    nft.transfer(&user1, &user2, &1);
+    nft.set_default_soulbound(&false);
+    assert!(nft.is_soulbound(&2));
+    mint(&e, &nft, &user1);
+    nft.transfer(&user1, &user2, &5);
+
+    // The owner can burn their own badge.
+    nft.burn(&user1, &2);
+    assert_eq!(nft.balance_of(&user1), 2);
+
+    // Only the admin can change the default.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_default_soulbound",
+                args: (true,).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_default_soulbound(&true);
+    assert!(result.is_err());
+    assert!(!nft.default_soulbound());
+}
+
+#[test]
+fn test_pause_and_unpause() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert!(!nft.paused());
+    assert_eq!(nft.try_unpause(), Err(Ok(Error::NotPaused)));
+
+    nft.pause();
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    symbol_short!("pause"),
+                    ().into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("paused"),).into_val(&e),
+                admin.into_val(&e),
+            ),
+        ]
+    );
+    assert!(nft.paused());
+    assert_eq!(nft.try_pause(), Err(Ok(Error::Paused)));
+
+    nft.unpause();
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("unpaused"),).into_val(&e),
+                admin.into_val(&e),
+            ),
+        ]
+    );
+    assert!(!nft.paused());
+    mint(&e, &nft, &user1);
+
+    // Only the admin can pause.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "pause",
+                args: ().into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_pause();
+    assert!(result.is_err());
+    assert!(!nft.paused());
+}
+
+#[test]
+fn test_paused_blocks_state_changes() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let admin = nft.admin();
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let text = String::from_str(&e, "Paused");
+
+    mint(&e, &nft, &user1);
+    nft.pause();
+
+    assert_eq!(
+        nft.try_mint(&admin, &user1, &text, &text, &text, &0, &0, &10, &false),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_mint_batch(&admin, &vec![&e, mint_request(&e, &user1, 0)]),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_create_drop(&text, &text, &text, &0, &0, &10, &1, &0, &5_000),
+        Err(Ok(Error::Paused))
+    );
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    assert_eq!(
+        nft.try_claim(&user1, &drop_id, &attestation, &signature),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(nft.try_transfer(&user1, &user2, &1), Err(Ok(Error::Paused)));
+    assert_eq!(
+        nft.try_transfer_batch(&user1, &user2, &vec![&e, 1]),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_transfer_from(&user1, &user1, &user2, &1),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_approve(&user1, &user2, &1, &1_000),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_set_approval_for_all(&user1, &user2, &1_000),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(nft.try_burn(&admin, &1), Err(Ok(Error::Paused)));
+    assert_eq!(
+        nft.try_update_metadata(&user1, &1, &text, &text, &text),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_freeze_metadata(&user1, &Some(1)),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_set_token_royalty(&1, &user1, &500),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_set_validity(&admin, &1, &None, &Some(0)),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_set_polygon(&admin, &1, &vec![&e, (0, 0), (0, 1), (1, 0)]),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        nft.try_update_location(&admin, &1, &0, &0, &10),
+        Err(Ok(Error::Paused))
+    );
+    let legacy = create_legacy_nft(&e);
+    legacy_mint(&e, &legacy, &user1, 9, "40.7829", "-73.9654");
+    assert_eq!(
+        nft.try_import_legacy(&legacy.address, &user1, &9),
+        Err(Ok(Error::Paused))
+    );
+
+    // Nothing changed.
+    assert_eq!(nft.total_supply(), 1);
+    assert_eq!(nft.owner_of(&1), user1);
+    assert_eq!(nft.get_drop(&drop_id).claimed, 0);
+    assert_eq!(nft.get_location(&1).radius, 100);
+    assert_eq!(
+        nft.try_royalty_info(&1, &10_000),
+        Err(Ok(Error::RoyaltyNotSet))
+    );
+
+    // Collection settings and maintenance still work, so an incident can be
+    // fixed before unpausing.
+    let metadata = collection_metadata(&e, "https://example.com/tokens/");
+    nft.set_collection_metadata(&metadata);
+    assert_eq!(nft.collection_metadata(), metadata);
+    nft.set_default_royalty(&user2, &500);
+    assert_eq!(nft.royalty_info(&1, &10_000), (user2.clone(), 500));
+    nft.set_default_soulbound(&true);
+    assert!(nft.default_soulbound());
+    nft.set_oracle(&BytesN::from_array(&e, &[1; 32]));
+    assert_eq!(nft.oracle(), BytesN::from_array(&e, &[1; 32]));
+    nft.set_max_radius(&1_000);
+    assert_eq!(nft.max_radius(), 1_000);
+    nft.bump_token(&1);
+    nft.bump_history(&1, &0, &10);
+
+    nft.unpause();
+    nft.transfer(&user1, &user2, &1);
+    assert_eq!(nft.owner_of(&1), user2);
+}
+
+#[test]
+fn test_storage_ttl_extended_on_write() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    let check_ttls = |owner: &Address| {
+        e.as_contract(&nft.address, || {
+            assert_eq!(e.storage().instance().get_ttl(), INSTANCE_BUMP_AMOUNT);
+            let storage = e.storage().persistent();
+            for key in [
+                symbol_short!("OWNER"),
+                symbol_short!("METADATA"),
+                symbol_short!("LOCATION"),
+                symbol_short!("OWNED_IDX"),
+            ] {
+                assert_eq!(storage.get_ttl(&(1_u32, key)), TOKEN_BUMP_AMOUNT);
+            }
+            let owned_key = (owner.clone(), 0_u32, symbol_short!("OWNED"));
+            assert_eq!(storage.get_ttl(&owned_key), TOKEN_BUMP_AMOUNT);
+            let balance_key = (owner.clone(), symbol_short!("BALANCE"));
+            assert_eq!(storage.get_ttl(&balance_key), TOKEN_BUMP_AMOUNT);
+        });
+    };
+    check_ttls(&user1);
+
+    // A transfer extends the token's entries again.
+    e.ledger().set_sequence_number(10 * DAY_IN_LEDGERS);
+    nft.transfer(&user1, &user2, &1);
+    check_ttls(&user2);
+}
+
+#[test]
+fn test_bump_token_keeps_token_alive() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    mint(&e, &nft, &user1);
+    nft.burn(&user1, &2);
+    assert_eq!(nft.try_bump_token(&2), Err(Ok(Error::TokenNotFound)));
+    nft.update_location(&admin, &1, &40_758_000, &-73_985_500, &50);
+
+    // Bump every 6 days until well past the 30 day token TTL.
+    for day in (6..=36).step_by(6) {
+        e.ledger().set_sequence_number(day * DAY_IN_LEDGERS);
+        nft.bump_token(&1);
+        // Anyone can bump a token.
+        assert_eq!(e.auths(), std::vec![]);
+        nft.bump_history(&1, &0, &10);
+        assert_eq!(e.auths(), std::vec![]);
+        // A burned token's history can still be kept alive.
+        nft.bump_history(&2, &0, &10);
+    }
+    e.as_contract(&nft.address, || {
+        let storage = e.storage().persistent();
+        assert_eq!(
+            storage.get_ttl(&(1_u32, symbol_short!("METADATA"))),
+            TOKEN_BUMP_AMOUNT
+        );
+        for token_id in 1..=2_u32 {
+            assert_eq!(
+                storage.get_ttl(&(token_id, symbol_short!("HIST_LEN"))),
+                TOKEN_BUMP_AMOUNT
+            );
+            assert_eq!(
+                storage.get_ttl(&(token_id, 0_u32, symbol_short!("HISTORY"))),
+                TOKEN_BUMP_AMOUNT
+            );
+        }
+        assert_eq!(
+            storage.get_ttl(&(1_u32, 1_u32, symbol_short!("HISTORY"))),
+            TOKEN_BUMP_AMOUNT
+        );
+    });
+
+    assert_eq!(nft.owner_of(&1), user1);
+    assert_eq!(nft.get_location(&1).radius, 50);
+    let history = nft.location_history(&1, &0, &10);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get(0).unwrap().radius, 100);
+    assert_eq!(nft.get_metadata(&1).symbol, String::from_str(&e, "CP"));
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 1]);
+    assert_eq!(nft.balance_of(&user1), 1);
+}
+
+#[test]
+fn test_bump_token_with_long_history() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    for i in 0..120 {
+        nft.update_location(&admin, &1, &(40_758_000 + i), &-73_985_500, &50);
+    }
+    assert_eq!(nft.location_history(&1, &120, &10).len(), 1);
+
+    // `bump_token` touches the same entries however long the history is.
+    e.ledger().set_sequence_number(6 * DAY_IN_LEDGERS);
+    nft.bump_token(&1);
+    e.as_contract(&nft.address, || {
+        let storage = e.storage().persistent();
+        assert_eq!(
+            storage.get_ttl(&(1_u32, symbol_short!("METADATA"))),
+            TOKEN_BUMP_AMOUNT
+        );
+        assert_eq!(
+            storage.get_ttl(&(1_u32, symbol_short!("HIST_LEN"))),
+            TOKEN_BUMP_AMOUNT
+        );
+        assert!(storage.get_ttl(&(1_u32, 0_u32, symbol_short!("HISTORY"))) < TOKEN_BUMP_AMOUNT);
+    });
+
+    // The records are bumped a page at a time, at most 50 per call.
+    nft.bump_history(&1, &0, &100);
+    e.as_contract(&nft.address, || {
+        let storage = e.storage().persistent();
+        assert_eq!(
+            storage.get_ttl(&(1_u32, 49_u32, symbol_short!("HISTORY"))),
+            TOKEN_BUMP_AMOUNT
+        );
+        assert!(storage.get_ttl(&(1_u32, 50_u32, symbol_short!("HISTORY"))) < TOKEN_BUMP_AMOUNT);
+    });
+    nft.bump_history(&1, &50, &50);
+    nft.bump_history(&1, &100, &50);
+    // Pages past the end extend nothing.
+    nft.bump_history(&1, &150, &50);
+    e.as_contract(&nft.address, || {
+        let storage = e.storage().persistent();
+        for index in [0_u32, 49, 50, 99, 100, 120] {
+            assert_eq!(
+                storage.get_ttl(&(1_u32, index, symbol_short!("HISTORY"))),
+                TOKEN_BUMP_AMOUNT
+            );
+        }
+    });
+}
+
+fn mint_request(e: &Env, to: &Address, latitude: i64) -> MintRequest {
+    MintRequest {
+        to: to.clone(),
+        name: String::from_str(e, "Campaign NFT"),
+        symbol: String::from_str(e, "CMP"),
+        uri: String::from_str(e, "https://example.com/campaign.json"),
+        latitude,
+        longitude: -73_965_400,
+        radius: 100,
+        soulbound: false,
+    }
+}
+
+#[test]
+fn test_mint_batch() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let requests = vec![
+        &e,
+        mint_request(&e, &user1, 40_782_900),
+        mint_request(&e, &user2, 40_783_000),
+        mint_request(&e, &user1, 40_783_100),
+    ];
+    assert_eq!(nft.mint_batch(&admin, &requests), vec![&e, 1, 2, 3]);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "mint_batch"),
+                    (&admin, requests.clone()).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("mint"), &user1).into_val(&e),
+                location_event_data(&e, Some(1), 40_782_900, -73_965_400, 100),
+            ),
+            (
+                nft.address.clone(),
+                (symbol_short!("mint"), &user2).into_val(&e),
+                location_event_data(&e, Some(2), 40_783_000, -73_965_400, 100),
+            ),
+            (
+                nft.address.clone(),
+                (symbol_short!("mint"), &user1).into_val(&e),
+                location_event_data(&e, Some(3), 40_783_100, -73_965_400, 100),
+            ),
+        ]
+    );
+
+    assert_eq!(nft.total_supply(), 3);
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 1, 3]);
+    assert_eq!(nft.owner_of(&2), user2);
+    assert_eq!(nft.get_location(&3).latitude, 40_783_100);
+    assert_eq!(
+        nft.get_metadata(&3).name,
+        String::from_str(&e, "Campaign NFT")
+    );
+}
+
+#[test]
+fn test_mint_batch_rolls_back_on_bad_item() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let requests = vec![
+        &e,
+        mint_request(&e, &user1, 40_782_900),
+        mint_request(&e, &user1, 40_783_000),
+        mint_request(&e, &user1, 91_000_000),
+    ];
+    assert_eq!(
+        nft.try_mint_batch(&admin, &requests),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+    assert_eq!(e.events().all(), vec![&e]);
+    assert_eq!(nft.total_supply(), 0);
+    assert_eq!(nft.balance_of(&user1), 0);
+    assert_eq!(nft.try_owner_of(&1), Err(Ok(Error::TokenNotFound)));
+    assert_eq!(nft.try_get_metadata(&2), Err(Ok(Error::TokenNotFound)));
+
+    // The id counter is rolled back too.
+    assert_eq!(mint(&e, &nft, &user1), 1);
+}
+
+#[test]
+fn test_transfer_batch() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    for _ in 0..3 {
+        mint(&e, &nft, &user1);
+    }
+    nft.approve(&user1, &admin, &1, &200);
+
+    nft.transfer_batch(&user1, &user2, &vec![&e, 1, 3]);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            user1.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "transfer_batch"),
+                    (&user1, &user2, vec![&e, 1_u32, 3_u32]).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (symbol_short!("transfer"), &user1, &user2).into_val(&e),
+                1_u32.into_val(&e),
+            ),
+            (
+                nft.address.clone(),
+                (symbol_short!("transfer"), &user1, &user2).into_val(&e),
+                3_u32.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.tokens_of_owner(&user1, &0, &10), vec![&e, 2]);
+    assert_eq!(nft.tokens_of_owner(&user2, &0, &10), vec![&e, 1, 3]);
+    assert_eq!(nft.get_approved(&1), None);
+
+    // user1 no longer owns token 1, so token 2 does not move either.
+    assert_eq!(
+        nft.try_transfer_batch(&user1, &user2, &vec![&e, 2, 1]),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(
+        nft.try_transfer_batch(&user1, &user2, &vec![&e, 2, 9]),
+        Err(Ok(Error::TokenNotFound))
+    );
+    assert_eq!(e.events().all(), vec![&e]);
+    assert_eq!(nft.owner_of(&2), user1);
+    assert_eq!(nft.balance_of(&user1), 1);
+    assert_eq!(nft.balance_of(&user2), 2);
+}
+
+#[test]
+fn test_batch_size_limit() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let mut requests = vec![&e];
+    for i in 0..MAX_MINT_BATCH as i64 {
+        requests.push_back(mint_request(&e, &user1, 40_782_900 + i * 1_000));
+    }
+    for _ in 0..2 {
+        nft.mint_batch(&admin, &requests);
+    }
+    requests.push_back(mint_request(&e, &user1, 40_782_900));
+    assert_eq!(
+        nft.try_mint_batch(&admin, &requests),
+        Err(Ok(Error::BatchTooLarge))
+    );
+
+    let mut token_ids = nft.tokens_of_owner(&user1, &0, &MAX_TRANSFER_BATCH);
+    nft.transfer_batch(&user1, &user2, &token_ids);
+    token_ids.push_back(nft.tokens_of_owner(&user1, &0, &1).get(0).unwrap());
+    assert_eq!(
+        nft.try_transfer_batch(&user2, &user1, &token_ids),
+        Err(Ok(Error::BatchTooLarge))
+    );
+    assert_eq!(nft.balance_of(&user2), MAX_TRANSFER_BATCH);
+}
+
+#[test]
+fn test_batch_footprint() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // The worst case for a mint: the caller mints through a role grant, and
+    // every token is soulbound and has its own owner and cell.
+    let minter = Address::generate(&e);
+    nft.grant_role(&Role::Minter, &minter);
+    let mut requests = vec![&e];
+    for i in 0..MAX_MINT_BATCH as i64 {
+        let mut request = mint_request(&e, &Address::generate(&e), 40_000_000 + i * 1_000);
+        request.soulbound = true;
+        requests.push_back(request);
+    }
+    nft.mint_batch(&minter, &requests);
+    let resources = e.cost_estimate().resources();
+    assert_eq!(
+        resources.memory_read_entries,
+        MINT_FOOTPRINT.accessed(MAX_MINT_BATCH)
+    );
+    assert_eq!(
+        resources.write_entries,
+        MINT_FOOTPRINT.writes(MAX_MINT_BATCH)
+    );
+
+    // The worst case for a transfer: every token has every optional record
+    // and its own cell, and removing it moves another of the sender's
+    // tokens into its slot.
+    for i in 0..2 * MAX_TRANSFER_BATCH as i64 {
+        let token_id = mint_at(&e, &nft, 41_000_000 + i * 1_000, 0, 100);
+        let owner = nft.owner_of(&token_id);
+        nft.transfer(&owner, &user1, &token_id);
+        nft.approve(&user1, &admin, &token_id, &1_000);
+        nft.set_token_royalty(&token_id, &admin, &500);
+        nft.freeze_metadata(&admin, &Some(token_id));
+        nft.set_polygon(&admin, &token_id, &vec![&e, (0, 0), (0, 1), (1, 0)]);
+    }
+    let token_ids = nft.tokens_of_owner(&user1, &0, &MAX_TRANSFER_BATCH);
+    nft.transfer_batch(&user1, &user2, &token_ids);
+    let resources = e.cost_estimate().resources();
+    assert_eq!(
+        resources.memory_read_entries,
+        TRANSFER_FOOTPRINT.accessed(MAX_TRANSFER_BATCH)
+    );
+    assert_eq!(
+        resources.write_entries,
+        TRANSFER_FOOTPRINT.writes(MAX_TRANSFER_BATCH)
+    );
+
+    // One more item would not fit.
+    for footprint in [MINT_FOOTPRINT, TRANSFER_FOOTPRINT] {
+        let items = footprint.max_items() + 1;
+        assert!(footprint.accessed(items) > 100 || footprint.writes(items) > 50);
+    }
+}
+
+#[test]
+fn test_update_metadata() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(1_000);
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(nft.get_metadata(&1).updated_at, 1_000);
+
+    e.ledger().set_timestamp(2_000);
+    let name = String::from_str(&e, "Central Park NFT");
+    let symbol = String::from_str(&e, "CP");
+    let uri = String::from_str(&e, "ipfs://bafyrepinned");
+    nft.update_metadata(&user1, &1, &name, &symbol, &uri);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            user1.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "update_metadata"),
+                    (&user1, 1_u32, &name, &symbol, &uri).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (symbol_short!("name"), name.into_val(&e)),
+        (symbol_short!("symbol"), symbol.into_val(&e)),
+        (symbol_short!("uri"), uri.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "metadata_updated"), 1_u32).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(
+        nft.get_metadata(&1),
+        TokenMetadata {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            latitude: 40_782_900,
+            longitude: -73_965_400,
+            radius: 100,
+            created_at: 1_000,
+            updated_at: 2_000,
+        }
+    );
+
+    // The admin can update any token; anyone else cannot.
+    e.ledger().set_timestamp(3_000);
+    let uri = String::from_str(&e, "ipfs://bafyadmin");
+    nft.update_metadata(&admin, &1, &name, &symbol, &uri);
+    assert_eq!(nft.get_metadata(&1).uri, uri);
+    assert_eq!(nft.get_metadata(&1).updated_at, 3_000);
+    assert_eq!(
+        nft.try_update_metadata(&user2, &1, &name, &symbol, &uri),
+        Err(Ok(Error::NotOwner))
+    );
+    assert_eq!(
+        nft.try_update_metadata(&user1, &2, &name, &symbol, &uri),
+        Err(Ok(Error::TokenNotFound))
+    );
+}
+
+#[test]
+fn test_freeze_metadata() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    mint(&e, &nft, &user2);
+    let name = String::from_str(&e, "Central Park NFT");
+    let symbol = String::from_str(&e, "CP");
+    let uri = String::from_str(&e, "ipfs://bafyrepinned");
+
+    assert_eq!(
+        nft.try_freeze_metadata(&user2, &Some(1)),
+        Err(Ok(Error::NotOwner))
+    );
+    nft.freeze_metadata(&user1, &Some(1));
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "metadata_frozen"), &user1).into_val(&e),
+                Some(1_u32).into_val(&e),
+            ),
+        ]
+    );
+    assert!(nft.metadata_frozen(&1));
+    assert!(!nft.metadata_frozen(&2));
+
+    // Not even the admin can update a frozen token.
+    assert_eq!(
+        nft.try_update_metadata(&user1, &1, &name, &symbol, &uri),
+        Err(Ok(Error::MetadataFrozen))
+    );
+    assert_eq!(
+        nft.try_update_metadata(&admin, &1, &name, &symbol, &uri),
+        Err(Ok(Error::MetadataFrozen))
+    );
+    nft.update_metadata(&user2, &2, &name, &symbol, &uri);
+
+    // Only the admin can freeze the whole collection, including later mints.
+    assert_eq!(
+        nft.try_freeze_metadata(&user2, &None),
+        Err(Ok(Error::NotOwner))
+    );
+    nft.freeze_metadata(&admin, &None);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "metadata_frozen"), &admin).into_val(&e),
+                ().into_val(&e),
+            ),
+        ]
+    );
+    mint(&e, &nft, &user2);
+    assert!(nft.metadata_frozen(&2));
+    assert!(nft.metadata_frozen(&3));
+    assert_eq!(
+        nft.try_update_metadata(&user2, &3, &name, &symbol, &uri),
+        Err(Ok(Error::MetadataFrozen))
+    );
+
+    // Freezing does not stop location updates.
+    nft.update_location(&admin, &1, &40_781_200, &-73_966_500, &250);
+    assert_eq!(nft.get_location(&1).radius, 250);
+}
+
+fn collection_metadata(e: &Env, base_uri: &str) -> CollectionMetadata {
+    CollectionMetadata {
+        description: String::from_str(e, "Places worth visiting"),
+        image: String::from_str(e, "https://example.com/collection.png"),
+        external_url: String::from_str(e, "https://example.com"),
+        base_uri: String::from_str(e, base_uri),
+    }
+}
+
+#[test]
+fn test_collection_metadata() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    assert_eq!(nft.collection_metadata().base_uri, String::from_str(&e, ""));
+
+    let metadata = collection_metadata(&e, "https://example.com/tokens/");
+    nft.set_collection_metadata(&metadata);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "set_collection_metadata"),
+                    (&metadata,).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (symbol_short!("base_uri"), metadata.base_uri.into_val(&e)),
+        (
+            Symbol::new(&e, "description"),
+            metadata.description.into_val(&e)
+        ),
+        (
+            Symbol::new(&e, "external_url"),
+            metadata.external_url.into_val(&e)
+        ),
+        (symbol_short!("image"), metadata.image.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "collection_metadata_updated"),).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.collection_metadata(), metadata);
+
+    // Base URIs that would not fit in a token URI are rejected.
+    let long_uri = "https://example.com/".repeat(10) + "x";
+    assert_eq!(
+        nft.try_set_collection_metadata(&collection_metadata(&e, &long_uri)),
+        Err(Ok(Error::InvalidUri))
+    );
+
+    // Only the admin can set the collection metadata.
+    let other = collection_metadata(&e, "https://attacker.example/");
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_collection_metadata",
+                args: (&other,).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_collection_metadata(&other);
+    assert!(result.is_err());
+    assert_eq!(nft.collection_metadata(), metadata);
+}
+
+#[test]
+fn test_token_uri() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+    let empty = String::from_str(&e, "");
+
+    // Token 1 has its own uri; token 2 relies on the collection.
+    mint(&e, &nft, &user1);
+    nft.mint(
+        &admin,
+        &user1,
+        &empty,
+        &empty,
+        &empty,
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    );
+    assert_eq!(
+        nft.token_uri(&1),
+        String::from_str(&e, "https://example.com/metadata.json")
+    );
+    assert_eq!(nft.token_uri(&2), empty);
+
+    nft.set_collection_metadata(&collection_metadata(&e, "https://example.com/tokens/"));
+    assert_eq!(
+        nft.token_uri(&1),
+        String::from_str(&e, "https://example.com/metadata.json")
+    );
+    assert_eq!(
+        nft.token_uri(&2),
+        String::from_str(&e, "https://example.com/tokens/2")
+    );
+
+    // Empty names and symbols are read from the collection.
+    let metadata = nft.get_metadata(&2);
+    assert_eq!(metadata.name, String::from_str(&e, "StellarGeoLinkNFT"));
+    assert_eq!(metadata.symbol, String::from_str(&e, "SGL"));
+    assert_eq!(nft.get_metadata(&1).symbol, String::from_str(&e, "CP"));
+
+    // Every digit of the largest token id is kept.
+    e.as_contract(&nft.address, || {
+        e.storage().instance().set(&TOKEN_ID_KEY, &(u32::MAX - 1));
+    });
+    let token_id = nft.mint(
+        &admin,
+        &user1,
+        &empty,
+        &empty,
+        &empty,
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    );
+    assert_eq!(token_id, u32::MAX);
+    assert_eq!(
+        nft.token_uri(&token_id),
+        String::from_str(&e, "https://example.com/tokens/4294967295")
+    );
+    assert_eq!(nft.try_token_uri(&3), Err(Ok(Error::TokenNotFound)));
+}
+
+#[test]
+fn test_cell_id() {
+    let e = Env::default();
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // Cells are named by their south-west corner, flooring negative values.
+    assert_eq!(
+        nft.cell_id(&40_782_900, &-73_965_400),
+        (40_782_000, -73_966_000)
+    );
+    assert_eq!(
+        nft.cell_id(&34_230_479, &-118_231_001),
+        (34_230_000, -118_232_000)
+    );
+    assert_eq!(nft.cell_id(&-1, &0), (-1_000, 0));
+    assert_eq!(
+        nft.cell_id(&90_000_000, &180_000_000),
+        (90_000_000, 180_000_000)
+    );
+    assert_eq!(
+        nft.try_cell_id(&90_000_001, &0),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+}
+
+#[test]
+fn test_tokens_in_cell() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let park = (40_782_000, -73_966_000);
+    let museum = (40_779_000, -73_964_000);
+    mint(&e, &nft, &user1);
+    mint_at(&e, &nft, 40_782_100, -73_965_900, 50);
+    mint_at(&e, &nft, 40_779_400, -73_963_200, 50);
+    assert_eq!(nft.tokens_in_cell(&park, &0, &10), vec![&e, 1, 2]);
+    assert_eq!(nft.tokens_in_cell(&park, &1, &1), vec![&e, 2]);
+    assert_eq!(nft.tokens_in_cell(&park, &2, &10), vec![&e]);
+    assert_eq!(nft.tokens_in_cell(&museum, &0, &10), vec![&e, 3]);
+    assert_eq!(nft.tokens_in_cell(&(0, 0), &0, &10), vec![&e]);
+
+    // Moving within a cell keeps the token's place; moving out re-indexes it.
+    nft.update_location(&admin, &1, &40_782_999, &-73_965_001, &100);
+    assert_eq!(nft.tokens_in_cell(&park, &0, &10), vec![&e, 1, 2]);
+    nft.update_location(&admin, &1, &40_779_100, &-73_963_900, &100);
+    assert_eq!(nft.tokens_in_cell(&park, &0, &10), vec![&e, 2]);
+    assert_eq!(nft.tokens_in_cell(&museum, &0, &10), vec![&e, 3, 1]);
+
+    // Transfers do not touch the index; burns remove the token.
+    nft.transfer(&user1, &admin, &1);
+    assert_eq!(nft.tokens_in_cell(&museum, &0, &10), vec![&e, 3, 1]);
+    nft.burn(&admin, &3);
+    assert_eq!(nft.tokens_in_cell(&museum, &0, &10), vec![&e, 1]);
+    nft.burn(&admin, &1);
+    assert_eq!(nft.tokens_in_cell(&museum, &0, &10), vec![&e]);
+    assert_eq!(nft.tokens_in_cell(&park, &0, &10), vec![&e, 2]);
+}
+
+#[test]
+fn test_tokens_in_cell_page_size_capped() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    for _ in 0..55 {
+        mint_at(&e, &nft, 0, 0, 10);
+    }
+    assert_eq!(nft.tokens_in_cell(&(0, 0), &0, &1_000).len(), 50);
+    let rest = nft.tokens_in_cell(&(0, 0), &50, &1_000);
+    assert_eq!(rest, vec![&e, 51, 52, 53, 54, 55]);
+}
+
+#[test]
+fn test_location_history() {
+    let e = Env::default();
+    e.mock_all_auths();
+    e.ledger().set_timestamp(1_000);
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    e.ledger().set_timestamp(2_000);
+    nft.update_location(&admin, &1, &40_781_200, &-73_966_500, &250);
+    e.ledger().set_timestamp(3_000);
+    nft.update_location(&admin, &1, &0, &0, &10);
+
+    let record = |latitude, longitude, radius, timestamp| LocationRecord {
+        latitude,
+        longitude,
+        radius,
+        timestamp,
+        updater: admin.clone(),
+    };
+    let history = vec![
+        &e,
+        record(40_782_900, -73_965_400, 100, 1_000),
+        record(40_781_200, -73_966_500, 250, 2_000),
+        record(0, 0, 10, 3_000),
+    ];
+    assert_eq!(nft.location_history(&1, &0, &10), history);
+    assert_eq!(
+        nft.location_history(&1, &1, &1),
+        vec![&e, history.get(1).unwrap()]
+    );
+    assert_eq!(nft.location_history(&1, &3, &10), vec![&e]);
+
+    // Failed updates are not recorded.
+    assert_eq!(
+        nft.try_update_location(&admin, &1, &91_000_000, &0, &10),
+        Err(Ok(Error::InvalidCoordinate))
+    );
+    assert_eq!(nft.location_history(&1, &0, &10).len(), 3);
+
+    // The history outlives the token.
+    nft.burn(&user1, &1);
+    assert_eq!(nft.location_history(&1, &0, &10), history);
+    assert_eq!(nft.location_history(&2, &0, &10), vec![&e]);
+}
+
+#[test]
+fn test_location_history_records_claimer() {
+    let e = Env::default();
+    let DropSetup {
+        nft,
+        oracle,
+        drop_id,
+    } = create_drop_setup(&e);
+    let user1 = Address::generate(&e);
+
+    let (attestation, signature) = attest(
+        &e,
+        &nft.address,
+        &oracle,
+        &user1,
+        drop_id,
+        40_782_900,
+        -73_965_400,
+        2_000,
+    );
+    let token_id = nft.claim(&user1, &drop_id, &attestation, &signature);
+    assert_eq!(
+        nft.location_history(&token_id, &0, &10),
+        vec![
+            &e,
+            LocationRecord {
+                latitude: 40_782_900,
+                longitude: -73_965_400,
+                radius: 100,
+                timestamp: 2_000,
+                updater: user1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_location_history_page_size_capped() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint_at(&e, &nft, 0, 0, 10);
+    for radius in 1..55 {
+        nft.update_location(&admin, &1, &0, &0, &radius);
+    }
+    assert_eq!(nft.location_history(&1, &0, &1_000).len(), 50);
+    let rest = nft.location_history(&1, &50, &1_000);
+    assert_eq!(rest.len(), 5);
+    assert_eq!(rest.get(4).unwrap().radius, 54);
+}
+
+#[test]
+fn test_royalty_info() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let creator = Address::generate(&e);
+    let artist = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    mint(&e, &nft, &user1);
+    assert_eq!(
+        nft.try_royalty_info(&1, &10_000),
+        Err(Ok(Error::RoyaltyNotSet))
+    );
+
+    nft.set_default_royalty(&creator, &500);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    nft.address.clone(),
+                    Symbol::new(&e, "set_default_royalty"),
+                    (&creator, 500_u32).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (Symbol::new(&e, "basis_points"), 500_u32.into_val(&e)),
+        (symbol_short!("receiver"), creator.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "royalty_set"), ()).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.royalty_info(&1, &10_000), (creator.clone(), 500));
+    // Amounts round down.
+    assert_eq!(nft.royalty_info(&2, &999), (creator.clone(), 49));
+    assert_eq!(nft.royalty_info(&2, &0), (creator.clone(), 0));
+
+    nft.set_token_royalty(&2, &artist, &1_000);
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (Symbol::new(&e, "basis_points"), 1_000_u32.into_val(&e)),
+        (symbol_short!("receiver"), artist.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                nft.address.clone(),
+                (Symbol::new(&e, "royalty_set"), 2_u32).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(nft.royalty_info(&2, &10_000), (artist.clone(), 1_000));
+    assert_eq!(nft.royalty_info(&1, &10_000), (creator.clone(), 500));
+
+    // Overrides survive transfers and are removed by burns.
+    nft.transfer(&user1, &creator, &2);
+    assert_eq!(nft.royalty_info(&2, &10_000), (artist.clone(), 1_000));
+    nft.burn(&creator, &2);
+    assert_eq!(
+        nft.try_royalty_info(&2, &10_000),
+        Err(Ok(Error::TokenNotFound))
+    );
+
+    // A 100% royalty on the largest price does not overflow.
+    nft.set_token_royalty(&1, &artist, &10_000);
+    assert_eq!(nft.royalty_info(&1, &i128::MAX), (artist, i128::MAX));
+}
+
+#[test]
+fn test_royalty_errors() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    mint(&e, &nft, &user1);
+    assert_eq!(
+        nft.try_set_default_royalty(&user1, &10_001),
+        Err(Ok(Error::InvalidRoyalty))
+    );
+    assert_eq!(
+        nft.try_set_token_royalty(&1, &user1, &10_001),
+        Err(Ok(Error::InvalidRoyalty))
+    );
+    assert_eq!(
+        nft.try_set_token_royalty(&2, &user1, &500),
+        Err(Ok(Error::TokenNotFound))
+    );
+    nft.set_default_royalty(&user1, &500);
+    assert_eq!(
+        nft.try_royalty_info(&1, &-1),
+        Err(Ok(Error::InvalidRoyalty))
+    );
+
+    // Only the admin can set royalties, even on the owner's own token.
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "set_token_royalty",
+                args: (1_u32, &user1, 5_000_u32).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_set_token_royalty(&1, &user1, &5_000);
+    assert!(result.is_err());
+    assert_eq!(nft.royalty_info(&1, &10_000), (user1, 500));
+}
+
+#[test]
+fn test_upgrade_requires_admin_auth() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    let wasm_hash = e.deployer().upload_contract_wasm(location_nft_v1::WASM);
+    let result = nft
+        .mock_auths(&[MockAuth {
+            address: &user1,
+            invoke: &MockAuthInvoke {
+                contract: &nft.address,
+                fn_name: "upgrade",
+                args: (wasm_hash.clone(),).into_val(&e),
+                sub_invokes: &[],
+            },
+        }])
+        .try_upgrade(&wasm_hash);
+    assert!(result.is_err());
+    assert_eq!(nft.version(), CONTRACT_VERSION);
+}
+
+#[test]
+fn test_migrate_current_storage() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let nft = create_nft(&e, &admin);
+
+    // Freshly initialized storage is already in the current layout.
+    assert_eq!(nft.try_migrate(), Err(Ok(Error::AlreadyMigrated)));
+
+    // Code without a migration to a version fails rather than trapping.
+    assert_eq!(
+        migrate_step(&e, CONTRACT_VERSION + 1),
+        Err(Error::UnknownVersion)
+    );
+}
+
+#[test]
+#[cfg(feature = "upgrade-tests")]
+fn test_upgrade_and_migrate() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let contract_id = e.register(location_nft_current::WASM, ());
+    let nft = location_nft_current::Client::new(&e, &contract_id);
+    nft.initialize(
+        &admin,
+        &String::from_str(&e, "StellarGeoLinkNFT"),
+        &String::from_str(&e, "SGL"),
+    );
+    let text = String::from_str(&e, "Central Park NFT");
+    let token_id = nft.mint(
+        &admin,
+        &user1,
+        &text,
+        &text,
+        &text,
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    );
+    assert_eq!(nft.version(), 1);
+
+    let wasm_hash = e.deployer().upload_contract_wasm(location_nft_next::WASM);
+    nft.upgrade(&wasm_hash);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    contract_id.clone(),
+                    symbol_short!("upgrade"),
+                    (wasm_hash.clone(),).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                contract_id.clone(),
+                (symbol_short!("upgraded"),).into_val(&e),
+                wasm_hash.into_val(&e),
+            ),
+        ]
+    );
+
+    // The same contract now runs the next build's code over its storage,
+    // which still records the old version until it is migrated.
+    let upgraded = location_nft_next::Client::new(&e, &contract_id);
+    assert_eq!(upgraded.version(), 2);
+    assert_eq!(upgraded.owner_of(&token_id), user1);
+    assert_eq!(upgraded.get_location(&token_id).latitude, 40_782_900);
+
+    upgraded.migrate();
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    contract_id.clone(),
+                    symbol_short!("migrate"),
+                    ().into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    let data: Map<Symbol, Val> = map![
+        &e,
+        (Symbol::new(&e, "from_version"), 1_u32.into_val(&e)),
+        (Symbol::new(&e, "to_version"), 2_u32.into_val(&e)),
+    ];
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                contract_id.clone(),
+                (symbol_short!("migrated"),).into_val(&e),
+                data.into_val(&e),
+            ),
+        ]
+    );
+    assert_eq!(
+        upgraded.try_migrate(),
+        Err(Ok(Error::AlreadyMigrated.into()))
+    );
+
+    // Tokens keep working under the new code, and the migrated counter
+    // keeps issuing new ids.
+    upgraded.transfer(&user1, &admin, &token_id);
+    assert_eq!(upgraded.owner_of(&token_id), admin);
+    let next_id = upgraded.mint(
+        &admin,
+        &user1,
+        &text,
+        &text,
+        &text,
+        &40_782_900,
+        &-73_965_400,
+        &100,
+        &false,
+    );
+    assert_eq!(next_id, token_id + 1);
+}
@@ -0,0 +1,47 @@
+//! Code upgrades and the storage migrations that follow them.
+//!
+//! The instance records the layout version its storage was written with.
+//! After `upgrade` installs new code, the admin calls `migrate` once to bring
+//! storage up to that code's `CONTRACT_VERSION`, one step per version.
+use crate::Error;
+use soroban_sdk::{symbol_short, Env};
+
+/// Version of this code's storage layout. The first release that can be
+/// upgraded is version 1; `initialize` records it.
+#[cfg(not(feature = "next-version"))]
+pub(crate) const CONTRACT_VERSION: u32 = 1;
+/// The `next-version` build, which the upgrade tests upgrade to, is one
+/// version ahead.
+#[cfg(feature = "next-version")]
+pub(crate) const CONTRACT_VERSION: u32 = 2;
+
+pub fn read_storage_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&symbol_short!("VERSION"))
+        .unwrap_or(CONTRACT_VERSION)
+}
+
+pub fn write_storage_version(env: &Env, version: u32) {
+    env.storage()
+        .instance()
+        .set(&symbol_short!("VERSION"), &version);
+}
+
+/// Migrates storage written at `version - 1` to `version`.
+#[cfg_attr(not(feature = "next-version"), allow(unused_variables))]
+pub fn migrate_step(env: &Env, version: u32) -> Result<(), Error> {
+    match version {
+        // The `next-version` build moves the token id counter to its new key.
+        #[cfg(feature = "next-version")]
+        2 => {
+            let storage = env.storage().instance();
+            if let Some(last_id) = storage.get::<_, u32>(&symbol_short!("TOKEN_ID")) {
+                storage.set(&crate::ownership::TOKEN_ID_KEY, &last_id);
+                storage.remove(&symbol_short!("TOKEN_ID"));
+            }
+            Ok(())
+        }
+        _ => Err(Error::UnknownVersion),
+    }
+}